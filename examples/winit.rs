@@ -6,7 +6,7 @@ use std::time::Instant;
 #[cfg(target_arch = "wasm32")]
 use web_time::Instant;
 
-use gengine_clipboard::{Clipboard, ClipboardEvent, ClipboardHandler};
+use gengine_clipboard::{Clipboard, ClipboardEvent, ClipboardHandler, ClipboardSnapshot};
 use softbuffer::{Context, Surface};
 use winit::{
 	application::ApplicationHandler,
@@ -21,14 +21,17 @@ struct ContextSurface {
 	_surface: Surface<Rc<Window>, Rc<Window>>,
 }
 
-struct ExampleConfig {
-	proxy: EventLoopProxy<ClipboardData>,
-}
+const WANTED_TYPES: [&str; 6] = [
+	"image/png",
+	"PNG",
+	"text/plain;charset=utf-8",
+	"UTF8_STRING",
+	"text/plain",
+	"CF_UNICODETEXT",
+];
 
-#[derive(Debug)]
-enum ClipboardData {
-	Text(String),
-	Png(Vec<u8>),
+struct ExampleConfig {
+	proxy: EventLoopProxy<ClipboardSnapshot>,
 }
 
 impl ClipboardHandler for ExampleConfig {
@@ -43,27 +46,35 @@ impl ClipboardHandler for ExampleConfig {
 			ClipboardEvent::PasteResult { source, data } => {
 				log::info!("Got mime types: {:?} from {source:?}", data.raw_types());
 
-				if let Some(bytes) = data.get_first_success(&["image/png", "PNG"]) {
-					let _ = self.proxy.send_event(ClipboardData::Png(bytes));
-				} else if let Some(string) = data.read_data::<String>() {
-					let _ = self.proxy.send_event(ClipboardData::Text(string));
-				} else {
+				let snapshot = data.snapshot(Some(&WANTED_TYPES));
+				if snapshot.is_empty() {
 					log::error!("Could not get wanted data for {source:?}");
-				};
+				} else {
+					let _ = self.proxy.send_event(snapshot);
+				}
+			}
+			ClipboardEvent::ClipboardChanged { source, data } => {
+				log::info!(
+					"Clipboard changed to {:?} from {source:?}",
+					data.raw_types()
+				);
+			}
+			ClipboardEvent::Expired { source } => {
+				log::info!("Clipboard cleared after its timeout {source:?}");
 			}
 		}
 	}
 }
 
 struct ExampleWindow {
-	proxy: EventLoopProxy<ClipboardData>,
+	proxy: EventLoopProxy<ClipboardSnapshot>,
 	context_surface: Option<ContextSurface>,
 	clipboard: Option<Clipboard>,
 	ctrl_left: bool,
 	ctrl_right: bool,
 }
 
-impl ApplicationHandler<ClipboardData> for ExampleWindow {
+impl ApplicationHandler<ClipboardSnapshot> for ExampleWindow {
 	fn resumed(&mut self, event_loop: &ActiveEventLoop) {
 		if self.context_surface.is_none() {
 			let window_attributes = Window::default_attributes().with_title("Clipboard Example");
@@ -125,12 +136,16 @@ impl ApplicationHandler<ClipboardData> for ExampleWindow {
 		}
 	}
 
-	fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: ClipboardData) {
-		match event {
-			ClipboardData::Text(text) => {
-				log::info!("Reseived from the string: {}", text);
+	fn user_event(&mut self, _event_loop: &ActiveEventLoop, snapshot: ClipboardSnapshot) {
+		let png = snapshot.as_data_access().get_canonical("image/png").ok();
+
+		match png {
+			None => {
+				if let Some(text) = snapshot.read_data::<String>() {
+					log::info!("Reseived from the string: {}", text);
+				}
 			}
-			ClipboardData::Png(png) => {
+			Some(png) => {
 				#[cfg(not(target_arch = "wasm32"))]
 				{
 					log::info!("Received a PNG from. Saving it into image.png.");
@@ -178,7 +193,7 @@ fn main() {
 			.filter_level(log::LevelFilter::Info)
 			.init();
 	}
-	let event_loop = EventLoop::<ClipboardData>::with_user_event()
+	let event_loop = EventLoop::<ClipboardSnapshot>::with_user_event()
 		.build()
 		.unwrap();
 
@@ -0,0 +1,203 @@
+//! Derive macros for the `ReadFromClipboard` and `WriteToClipboard` traits of gengine_clipboard.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize, ReadFromClipboard, WriteToClipboard)]
+//! #[clipboard(mime = "application/x-gengine-prefab", codec = "json")]
+//! #[clipboard(fallback = String)]
+//! struct Prefab {
+//!     name: String,
+//! }
+//! ```
+//!
+//! - `mime` is the format the type is stored as. It is required.
+//! - `codec` is how the type is turned into bytes, either `json`, which is the default, or
+//!   `bincode`, which needs the `bincode` feature. The type has to implement `Serialize` and
+//!   `Deserialize`.
+//! - `fallback` is another clipboard type, which is used when the own format is not available.
+//!   Reading needs `TryFrom<Fallback>` for the type, writing needs `From<&Type>` for the
+//!   fallback. It can be given multiple times, the first available fallback is read.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{DeriveInput, LitStr, Type, parse_macro_input};
+
+#[derive(Clone, Copy)]
+enum Codec {
+	Json,
+	Bincode,
+}
+
+impl Codec {
+	/// Paths of the encode and decode helpers in `gengine_clipboard::__derive`.
+	fn helpers(self) -> (TokenStream2, TokenStream2) {
+		match self {
+			Codec::Json => (
+				quote!(::gengine_clipboard::__derive::to_json),
+				quote!(::gengine_clipboard::__derive::from_json),
+			),
+			Codec::Bincode => (
+				quote!(::gengine_clipboard::__derive::to_bincode),
+				quote!(::gengine_clipboard::__derive::from_bincode),
+			),
+		}
+	}
+}
+
+struct Attributes {
+	mime: LitStr,
+	codec: Codec,
+	fallbacks: Vec<Type>,
+}
+
+impl Attributes {
+	fn parse(input: &DeriveInput) -> syn::Result<Self> {
+		let mut mime = None;
+		let mut codec = Codec::Json;
+		let mut fallbacks = Vec::new();
+
+		for attribute in &input.attrs {
+			if !attribute.path().is_ident("clipboard") {
+				continue;
+			}
+
+			attribute.parse_nested_meta(|meta| {
+				if meta.path.is_ident("mime") {
+					mime = Some(meta.value()?.parse::<LitStr>()?);
+				} else if meta.path.is_ident("codec") {
+					let value = meta.value()?.parse::<LitStr>()?;
+					codec = match value.value().as_str() {
+						"json" => Codec::Json,
+						"bincode" => Codec::Bincode,
+						_ => {
+							return Err(syn::Error::new(
+								value.span(),
+								"unsupported codec, expected \"json\" or \"bincode\"",
+							));
+						}
+					};
+				} else if meta.path.is_ident("fallback") {
+					fallbacks.push(meta.value()?.parse::<Type>()?);
+				} else {
+					return Err(meta.error("unknown clipboard attribute"));
+				}
+
+				Ok(())
+			})?;
+		}
+
+		let mime = mime.ok_or_else(|| {
+			syn::Error::new_spanned(
+				&input.ident,
+				"missing #[clipboard(mime = \"...\")] attribute",
+			)
+		})?;
+
+		Ok(Self {
+			mime,
+			codec,
+			fallbacks,
+		})
+	}
+}
+
+#[proc_macro_derive(ReadFromClipboard, attributes(clipboard))]
+pub fn derive_read_from_clipboard(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	expand_read(&input)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+#[proc_macro_derive(WriteToClipboard, attributes(clipboard))]
+pub fn derive_write_to_clipboard(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	expand_write(&input)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+fn expand_read(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let Attributes {
+		mime,
+		codec,
+		fallbacks,
+	} = Attributes::parse(input)?;
+	let (_, decode) = codec.helpers();
+	let name = &input.ident;
+	let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+	Ok(quote! {
+		impl #impl_generics ::gengine_clipboard::ReadFromClipboard for #name #type_generics #where_clause {
+			fn is_available(mime_types: &[&str]) -> bool {
+				let names = ::gengine_clipboard::mime_aliases::names_of(#mime);
+				mime_types.iter().any(|mime_type| names.iter().any(|name| name == mime_type))
+					#(|| <#fallbacks as ::gengine_clipboard::ReadFromClipboard>::is_available(mime_types))*
+			}
+
+			fn read(data: &::gengine_clipboard::DataAccess) -> ::core::option::Option<Self> {
+				// No let chains here, as the code is compiled with the edition of the caller.
+				let value = data
+					.get_canonical(#mime)
+					.ok()
+					.and_then(|bytes| #decode(&bytes));
+				if value.is_some() {
+					return value;
+				}
+
+				#(
+					let value = data
+						.read_data::<#fallbacks>()
+						.and_then(|fallback| <Self as ::core::convert::TryFrom<#fallbacks>>::try_from(fallback).ok());
+					if value.is_some() {
+						return value;
+					}
+				)*
+
+				::core::option::Option::None
+			}
+		}
+	})
+}
+
+fn expand_write(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let Attributes {
+		mime,
+		codec,
+		fallbacks,
+	} = Attributes::parse(input)?;
+	let (encode, _) = codec.helpers();
+	let name = &input.ident;
+	let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+	Ok(quote! {
+		impl #impl_generics ::gengine_clipboard::WriteToClipboard for #name #type_generics #where_clause {
+			fn viable_conversions(&self) -> ::std::vec::Vec<::std::string::String> {
+				let mut conversions = ::std::vec![::std::string::String::from(#mime)];
+				#(
+					for conversion in ::gengine_clipboard::WriteToClipboard::viable_conversions(&<#fallbacks>::from(self)) {
+						if !conversions.contains(&conversion) {
+							conversions.push(conversion);
+						}
+					}
+				)*
+				conversions
+			}
+
+			fn convert_to(&self, mime_type: &str) -> ::core::option::Option<::std::vec::Vec<u8>> {
+				if mime_type == #mime {
+					return #encode(self);
+				}
+
+				#(
+					let fallback = <#fallbacks>::from(self);
+					if ::gengine_clipboard::WriteToClipboard::viable_conversions(&fallback).iter().any(|conversion| conversion == mime_type) {
+						return ::gengine_clipboard::WriteToClipboard::convert_to(&fallback, mime_type);
+					}
+				)*
+
+				::core::option::Option::None
+			}
+		}
+	})
+}
@@ -0,0 +1,25 @@
+//! Helpers for the code generated by the derive macros. Not part of the public API.
+
+use serde::{Serialize, de::DeserializeOwned};
+
+pub fn to_json<T: Serialize>(value: &T) -> Option<Vec<u8>> {
+	serde_json::to_vec(value)
+		.inspect_err(|error| log::error!("Failed to encode clipboard data as JSON: {error}"))
+		.ok()
+}
+
+pub fn from_json<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+	serde_json::from_slice(bytes).ok()
+}
+
+#[cfg(feature = "bincode")]
+pub fn to_bincode<T: Serialize>(value: &T) -> Option<Vec<u8>> {
+	bincode::serialize(value)
+		.inspect_err(|error| log::error!("Failed to encode clipboard data as bincode: {error}"))
+		.ok()
+}
+
+#[cfg(feature = "bincode")]
+pub fn from_bincode<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+	bincode::deserialize(bytes).ok()
+}
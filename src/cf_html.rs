@@ -0,0 +1,208 @@
+//! The Windows "HTML Format" clipboard payload.
+//!
+//! The payload is UTF-8 text, starting with a header of `Key:Value` lines. The `StartHTML`,
+//! `EndHTML`, `StartFragment` and `EndFragment` values are byte offsets into the whole payload.
+//! See <https://learn.microsoft.com/en-us/windows/win32/dataxchg/html-clipboard-format>.
+
+/// The parts of a parsed payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CfHtml {
+	pub html: String,
+	pub fragment: String,
+	pub source_url: Option<String>,
+}
+
+const START_FRAGMENT_COMMENT: &str = "<!--StartFragment-->";
+const END_FRAGMENT_COMMENT: &str = "<!--EndFragment-->";
+
+/// Returns the part between the fragment comments, which most programs put into HTML as well.
+pub(crate) fn fragment_between_comments(html: &str) -> Option<&str> {
+	let start = html.find(START_FRAGMENT_COMMENT)? + START_FRAGMENT_COMMENT.len();
+	let end = start + html[start..].find(END_FRAGMENT_COMMENT)?;
+	Some(&html[start..end])
+}
+
+pub(crate) fn parse(bytes: &[u8]) -> Option<CfHtml> {
+	// Some programs include the terminating NUL.
+	let end = bytes
+		.iter()
+		.position(|byte| *byte == 0)
+		.unwrap_or(bytes.len());
+	let bytes = &bytes[..end];
+
+	let mut start_html = None;
+	let mut end_html = None;
+	let mut start_fragment = None;
+	let mut end_fragment = None;
+	let mut source_url = None;
+
+	// The header ends with the first line, which is not a known key.
+	let mut header_end = 0;
+	for line in bytes.split_inclusive(|byte| *byte == b'\n') {
+		let text = String::from_utf8_lossy(line);
+		let Some((key, value)) = text.trim_end_matches(['\r', '\n']).split_once(':') else {
+			break;
+		};
+
+		let offset = || {
+			value
+				.trim()
+				.parse::<i64>()
+				.ok()
+				.and_then(|offset| usize::try_from(offset).ok())
+		};
+		match key {
+			"Version" => {}
+			"StartHTML" => start_html = offset(),
+			"EndHTML" => end_html = offset(),
+			"StartFragment" => start_fragment = offset(),
+			"EndFragment" => end_fragment = offset(),
+			"StartSelection" | "EndSelection" => {}
+			"SourceURL" => source_url = Some(value.trim().to_string()),
+			_ => break,
+		}
+
+		header_end += line.len();
+	}
+
+	let slice = |start: Option<usize>, end: Option<usize>| {
+		let (start, end) = (start?, end?);
+		(start <= end).then(|| bytes.get(start..end)).flatten()
+	};
+
+	// StartHTML is -1, when the payload only contains the fragment.
+	let html =
+		slice(start_html, end_html).unwrap_or_else(|| bytes.get(header_end..).unwrap_or(&[]));
+	let html = String::from_utf8_lossy(html).into_owned();
+
+	let fragment = match slice(start_fragment, end_fragment) {
+		Some(fragment) => String::from_utf8_lossy(fragment).into_owned(),
+		None => fragment_between_comments(&html)?.to_string(),
+	};
+
+	Some(CfHtml {
+		html,
+		fragment,
+		source_url,
+	})
+}
+
+/// Width of the offsets in the header, so the header length is known before the offsets are.
+const OFFSET_WIDTH: usize = 10;
+
+fn header(offsets: [usize; 4], source_url: Option<&str>) -> String {
+	let [start_html, end_html, start_fragment, end_fragment] = offsets;
+	let mut header = format!(
+		"Version:0.9\r\nStartHTML:{start_html:0OFFSET_WIDTH$}\r\nEndHTML:{end_html:0OFFSET_WIDTH$}\r\n\
+		StartFragment:{start_fragment:0OFFSET_WIDTH$}\r\nEndFragment:{end_fragment:0OFFSET_WIDTH$}\r\n"
+	);
+	if let Some(source_url) = source_url {
+		header.push_str(&format!("SourceURL:{source_url}\r\n"));
+	}
+
+	header
+}
+
+/// Creates a payload from `html`, in which `fragment` is marked as the copied part.
+///
+/// The fragment comments in `html` are used if there are any. Otherwise they are put around
+/// the first occurrence of `fragment`, or `fragment` is wrapped into a document of its own.
+pub(crate) fn encode(html: &str, fragment: &str, source_url: Option<&str>) -> Vec<u8> {
+	let document = if fragment_between_comments(html).is_some() {
+		html.to_string()
+	} else if let Some(start) = html.find(fragment).filter(|_| !fragment.is_empty()) {
+		let end = start + fragment.len();
+		format!(
+			"{}{START_FRAGMENT_COMMENT}{fragment}{END_FRAGMENT_COMMENT}{}",
+			&html[..start],
+			&html[end..]
+		)
+	} else {
+		format!(
+			"<html><body>\r\n{START_FRAGMENT_COMMENT}{fragment}{END_FRAGMENT_COMMENT}\r\n</body></html>"
+		)
+	};
+
+	// Line breaks in the URL would end the header early.
+	let source_url = source_url.filter(|url| !url.contains(['\r', '\n']));
+
+	let start_html = header([0; 4], source_url).len();
+	let start_fragment = start_html
+		+ document.find(START_FRAGMENT_COMMENT).unwrap_or_default()
+		+ START_FRAGMENT_COMMENT.len();
+	let end_fragment = start_html + document.find(END_FRAGMENT_COMMENT).unwrap_or_default();
+	let end_html = start_html + document.len();
+
+	let mut bytes = header(
+		[start_html, end_html, start_fragment, end_fragment],
+		source_url,
+	)
+	.into_bytes();
+	bytes.extend_from_slice(document.as_bytes());
+	bytes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_offsets() {
+		let payload = "Version:0.9\r\n\
+			StartHTML:0000000137\r\n\
+			EndHTML:0000000216\r\n\
+			StartFragment:0000000171\r\n\
+			EndFragment:0000000182\r\n\
+			SourceURL:https://example.com/\r\n\
+			<html><body>\r\n<!--StartFragment--><b>bold</b><!--EndFragment-->\r\n</body></html>";
+
+		let parsed = parse(payload.as_bytes()).unwrap();
+		assert_eq!(parsed.fragment, "<b>bold</b>");
+		assert!(parsed.html.starts_with("<html>"));
+		assert!(parsed.html.ends_with("</html>"));
+		assert_eq!(parsed.source_url.as_deref(), Some("https://example.com/"));
+	}
+
+	#[test]
+	fn falls_back_to_comments_without_start_html() {
+		let payload = b"Version:1.0\r\nStartHTML:-1\r\nEndHTML:-1\r\nStartFragment:-1\r\n\
+			EndFragment:-1\r\n<!--StartFragment-->text<!--EndFragment-->\0";
+
+		let parsed = parse(payload).unwrap();
+		assert_eq!(parsed.fragment, "text");
+		assert_eq!(parsed.html, "<!--StartFragment-->text<!--EndFragment-->");
+		assert_eq!(parsed.source_url, None);
+	}
+
+	#[test]
+	fn rejects_out_of_range_offsets_without_comments() {
+		let payload = b"Version:0.9\r\nStartHTML:10\r\nEndHTML:9999\r\nStartFragment:10\r\n\
+			EndFragment:9999\r\n<p>x</p>";
+
+		assert_eq!(parse(payload), None);
+	}
+
+	#[test]
+	fn encodes_offsets() {
+		let payload = encode(
+			"<p>a <b>bold</b> move</p>",
+			"<b>bold</b>",
+			Some("https://example.com/"),
+		);
+		let text = String::from_utf8(payload.clone()).unwrap();
+		assert!(text.starts_with("Version:0.9\r\nStartHTML:00000001"));
+		assert!(text.contains("SourceURL:https://example.com/\r\n<p>a <!--StartFragment-->"));
+
+		let parsed = parse(&payload).unwrap();
+		assert_eq!(parsed.fragment, "<b>bold</b>");
+		assert_eq!(
+			parsed.html,
+			"<p>a <!--StartFragment--><b>bold</b><!--EndFragment--> move</p>"
+		);
+		assert_eq!(parsed.source_url.as_deref(), Some("https://example.com/"));
+
+		let parsed = parse(&encode("<i>x</i>", "ü", None)).unwrap();
+		assert_eq!(parsed.fragment, "ü");
+		assert!(parsed.html.starts_with("<html><body>"));
+	}
+}
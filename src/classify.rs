@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+
+use crate::{ClipboardFiles, ClipboardHtml, ReadFromClipboard, Rtf, mime_aliases};
+
+/// Raw types, which only carry a link.
+const URL_TYPES: [&str; 5] = [
+	"text/x-moz-url",
+	"_NETSCAPE_URL",
+	"text/x-uri",
+	"UniformResourceLocator",
+	"UniformResourceLocatorW",
+];
+
+/// Raw types of colors, as GTK and Qt offer them.
+const COLOR_TYPES: [&str; 1] = ["application/x-color"];
+
+/// X11 targets, which ask the selection owner about the selection or make it act, instead of
+/// returning content.
+pub(crate) const SELECTION_TARGETS: [&str; 7] = [
+	"TARGETS",
+	"TIMESTAMP",
+	"MULTIPLE",
+	"SAVE_TARGETS",
+	"DELETE",
+	"INSERT_SELECTION",
+	"INSERT_PROPERTY",
+];
+
+/// Raw types, which describe other content instead of being content.
+const METADATA_TYPES: [&str; 13] = [
+	"application/x-kde-cutselection",
+	"x-kde-passwordManagerHint",
+	"text/x-moz-url-priv",
+	"chromium/x-source-url",
+	"chromium/x-renderer-taint",
+	"Preferred DropEffect",
+	"CF_LOCALE",
+	"Ole Private Data",
+	"DataObject",
+	"ExcludeClipboardContentFromMonitorProcessing",
+	"CanIncludeInClipboardHistory",
+	"CanUploadToCloudClipboard",
+	"Shell IDList Array",
+];
+
+/// What kind of content the clipboard holds, as far as the offered raw types tell.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ClipboardContentKind {
+	Text,
+	/// HTML or RTF.
+	RichText,
+	Image,
+	Files,
+	Url,
+	Color,
+	/// A format none of the other kinds cover, under its canonical mime type.
+	Custom(String),
+}
+
+impl ClipboardContentKind {
+	fn of(raw_type: &str) -> Option<Self> {
+		if SELECTION_TARGETS.contains(&raw_type) || METADATA_TYPES.contains(&raw_type) {
+			return None;
+		}
+
+		let canonical = mime_aliases::canonical_type(raw_type);
+		let types = [raw_type];
+		let kind = if String::is_available(&types) {
+			Self::Text
+		} else if ClipboardHtml::is_available(&types) || Rtf::is_available(&types) {
+			Self::RichText
+		} else if canonical.starts_with("image/")
+			|| matches!(raw_type, "CF_DIB" | "CF_DIBV5" | "CF_BITMAP")
+		{
+			Self::Image
+		} else if ClipboardFiles::is_available(&types) {
+			Self::Files
+		} else if URL_TYPES.contains(&raw_type) {
+			Self::Url
+		} else if COLOR_TYPES.contains(&raw_type) {
+			Self::Color
+		} else {
+			Self::Custom(canonical)
+		};
+
+		Some(kind)
+	}
+}
+
+pub(crate) fn classify(raw_types: &[&str]) -> BTreeSet<ClipboardContentKind> {
+	raw_types
+		.iter()
+		.filter_map(|raw_type| ClipboardContentKind::of(raw_type))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ClipboardContentKind::*;
+
+	#[test]
+	fn classifies_browser_content() {
+		let kinds = classify(&[
+			"TARGETS",
+			"text/html",
+			"text/x-moz-url-priv",
+			"UTF8_STRING",
+			"text/plain",
+			"image/png",
+		]);
+		assert_eq!(kinds, BTreeSet::from([Text, RichText, Image]));
+	}
+
+	#[test]
+	fn classifies_windows_formats() {
+		let kinds = classify(&[
+			"CF_HDROP",
+			"Preferred DropEffect",
+			"UniformResourceLocatorW",
+			"PNG",
+			"Level Data",
+		]);
+		assert_eq!(
+			kinds,
+			BTreeSet::from([Image, Files, Url, Custom("Level Data".to_string())])
+		);
+	}
+}
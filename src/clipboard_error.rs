@@ -15,6 +15,10 @@ pub enum ClipboardError {
 	InUse,
 	WriteFailed,
 	UnsupportedMimeType,
+	/// The clipboard content changed since the data was offered.
+	Stale,
+	/// The operation is not supported on this platform.
+	Unsupported,
 	#[cfg(not(target_arch = "wasm32"))]
 	Unknown(String),
 }
@@ -37,6 +41,8 @@ impl ClipboardError {
 			11 => Some(Self::InUse),
 			12 => Some(Self::WriteFailed),
 			13 => Some(Self::UnsupportedMimeType),
+			14 => Some(Self::Stale),
+			15 => Some(Self::Unsupported),
 			_ => None,
 		}
 	}
@@ -60,6 +66,8 @@ impl From<ClipboardError> for u32 {
 			ClipboardError::InUse => 11,
 			ClipboardError::WriteFailed => 12,
 			ClipboardError::UnsupportedMimeType => 13,
+			ClipboardError::Stale => 14,
+			ClipboardError::Unsupported => 15,
 		}
 	}
 }
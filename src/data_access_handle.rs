@@ -0,0 +1,115 @@
+use crate::{
+	ClipboardError, ClipboardSnapshot, DataAccess, DataAccessInternal, ReadFromClipboard,
+	internal::InternalDataAccess, platform,
+};
+
+/// Handle to clipboard content, which can be sent to other threads and read later.
+///
+/// Data is fetched on demand by the clipboard backend. If the clipboard content changed since
+/// the handle was created, reading fails with [`ClipboardError::Stale`].
+///
+/// Reading from the thread that runs the [`crate::ClipboardHandler`] fails with
+/// [`ClipboardError::InUse`] on some platforms, as that thread would have to answer itself.
+#[derive(Clone)]
+pub struct DataAccessHandle {
+	pub(crate) internal: DataAccessHandleInternal,
+}
+
+#[derive(Clone)]
+pub(crate) enum DataAccessHandleInternal {
+	Platform(platform::DataAccessHandle),
+	Snapshot(ClipboardSnapshot),
+}
+
+impl DataAccessHandle {
+	pub(crate) fn internal(&self) -> &dyn InternalDataAccess {
+		match &self.internal {
+			DataAccessHandleInternal::Platform(internal) => internal,
+			DataAccessHandleInternal::Snapshot(snapshot) => snapshot,
+		}
+	}
+
+	pub fn raw_types(&self) -> Vec<&str> {
+		self.internal()
+			.mime_types()
+			.iter()
+			.map(|ty| ty.as_str())
+			.collect()
+	}
+
+	pub fn get_raw_data(&self, raw_type: &str) -> Result<Vec<u8>, ClipboardError> {
+		self.internal().get_raw_data(raw_type)
+	}
+
+	pub fn is_available<T: ReadFromClipboard>(&self) -> bool {
+		T::is_available(&self.raw_types())
+	}
+
+	pub fn read_data<T: ReadFromClipboard>(&self) -> Option<T> {
+		T::read(&self.as_data_access())
+	}
+
+	/// Wraps the handle into a [`DataAccess`], so it can be passed to code expecting one.
+	pub fn as_data_access(&self) -> DataAccess {
+		DataAccess {
+			internal: DataAccessInternal::Handle(self.clone()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::thread;
+
+	use super::*;
+
+	fn handle() -> DataAccessHandle {
+		ClipboardSnapshot::new(vec![
+			("text/plain;charset=utf-8".to_string(), b"hello".to_vec()),
+			("image/png".to_string(), vec![0x89, b'P', b'N', b'G']),
+		])
+		.as_data_access()
+		.handle()
+	}
+
+	#[test]
+	fn reads_snapshots() {
+		let handle = handle();
+		assert_eq!(
+			handle.raw_types(),
+			["text/plain;charset=utf-8", "image/png"]
+		);
+		assert_eq!(handle.get_raw_data("image/png").unwrap(), b"\x89PNG");
+		assert!(matches!(
+			handle.get_raw_data("text/html"),
+			Err(ClipboardError::FormatNotAvailable)
+		));
+		assert!(handle.is_available::<String>());
+		assert_eq!(handle.read_data::<String>().as_deref(), Some("hello"));
+	}
+
+	#[test]
+	fn reads_from_other_threads() {
+		let handle = handle();
+		let text = thread::spawn(move || handle.read_data::<String>())
+			.join()
+			.unwrap();
+		assert_eq!(text.as_deref(), Some("hello"));
+	}
+
+	#[test]
+	fn wraps_into_data_access() {
+		let data_access = handle().as_data_access();
+		assert_eq!(
+			data_access.raw_types(),
+			["text/plain;charset=utf-8", "image/png"]
+		);
+		assert_eq!(data_access.read_data::<String>().as_deref(), Some("hello"));
+
+		let handle = data_access.handle();
+		assert!(matches!(
+			handle.internal,
+			DataAccessHandleInternal::Snapshot(_)
+		));
+	}
+}
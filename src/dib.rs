@@ -0,0 +1,461 @@
+//! Device independent bitmaps, as used by `CF_DIB`, `CF_DIBV5` and BMP files.
+//!
+//! Decoding supports the BITMAPCOREHEADER, BITMAPINFOHEADER and its V2 to V5 extensions with
+//! 1, 4, 8, 16, 24 and 32 bits per pixel, uncompressed or with bitfields. RLE, JPEG and PNG
+//! compressed bitmaps are not supported.
+
+use crate::ClipboardImage;
+
+const CORE_HEADER_SIZE: usize = 12;
+const INFO_HEADER_SIZE: usize = 40;
+const V5_HEADER_SIZE: usize = 124;
+const FILE_HEADER_SIZE: usize = 14;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+/// "sRGB" as a little endian u32.
+const LCS_SRGB: u32 = 0x7352_4742;
+const LCS_GM_IMAGES: u32 = 4;
+
+/// Bitmaps larger than this are rejected, instead of allocating for them.
+const MAX_PIXELS: u64 = 1 << 28;
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+	bytes
+		.get(offset..offset + 2)
+		.map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+	bytes
+		.get(offset..offset + 4)
+		.map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Extracts a colour channel, selected by a bitfield mask, and scales it to 8 bits.
+#[derive(Clone, Copy)]
+struct Channel {
+	mask: u32,
+	shift: u32,
+	max: u32,
+}
+
+impl Channel {
+	fn new(mask: u32) -> Self {
+		let shift = if mask == 0 { 0 } else { mask.trailing_zeros() };
+		Self {
+			mask,
+			shift,
+			max: mask >> shift,
+		}
+	}
+
+	fn get(&self, pixel: u32) -> Option<u8> {
+		if self.max == 0 {
+			return None;
+		}
+
+		let value = (pixel & self.mask) >> self.shift;
+		Some(((value as u64 * 255 + self.max as u64 / 2) / self.max as u64) as u8)
+	}
+}
+
+struct Masks {
+	red: Channel,
+	green: Channel,
+	blue: Channel,
+	alpha: Channel,
+}
+
+impl Masks {
+	fn new(red: u32, green: u32, blue: u32, alpha: u32) -> Self {
+		Self {
+			red: Channel::new(red),
+			green: Channel::new(green),
+			blue: Channel::new(blue),
+			alpha: Channel::new(alpha),
+		}
+	}
+
+	fn rgba(&self, pixel: u32) -> [u8; 4] {
+		[
+			self.red.get(pixel).unwrap_or(0),
+			self.green.get(pixel).unwrap_or(0),
+			self.blue.get(pixel).unwrap_or(0),
+			self.alpha.get(pixel).unwrap_or(255),
+		]
+	}
+}
+
+/// Decodes a BMP file, which is a DIB with a 14 byte file header in front.
+pub(crate) fn decode_bmp(bytes: &[u8]) -> Option<ClipboardImage> {
+	if !bytes.starts_with(b"BM") {
+		return None;
+	}
+
+	let pixel_offset = u32_at(bytes, 10)? as usize;
+	decode_with_pixel_offset(
+		bytes.get(FILE_HEADER_SIZE..)?,
+		Some(pixel_offset.checked_sub(FILE_HEADER_SIZE)?),
+	)
+}
+
+/// Decodes a packed DIB, where the pixels directly follow the header, masks and palette.
+pub(crate) fn decode(bytes: &[u8]) -> Option<ClipboardImage> {
+	decode_with_pixel_offset(bytes, None)
+}
+
+fn decode_with_pixel_offset(bytes: &[u8], pixel_offset: Option<usize>) -> Option<ClipboardImage> {
+	let header_size = u32_at(bytes, 0)? as usize;
+
+	let (width, height, bit_count, compression, size_image, colors_used, palette_entry_size) =
+		if header_size == CORE_HEADER_SIZE {
+			let width = u16_at(bytes, 4)? as i32;
+			let height = u16_at(bytes, 6)? as i32;
+			(width, height, u16_at(bytes, 10)?, BI_RGB, 0, 0, 3)
+		} else if header_size >= INFO_HEADER_SIZE {
+			(
+				u32_at(bytes, 4)? as i32,
+				u32_at(bytes, 8)? as i32,
+				u16_at(bytes, 14)?,
+				u32_at(bytes, 16)?,
+				u32_at(bytes, 20)? as usize,
+				u32_at(bytes, 32)? as usize,
+				4,
+			)
+		} else {
+			return None;
+		};
+
+	if width <= 0 || height == 0 {
+		return None;
+	}
+	let top_down = height < 0;
+	let width = width as usize;
+	let height = height.unsigned_abs() as usize;
+	if width as u64 * height as u64 > MAX_PIXELS {
+		return None;
+	}
+
+	let mut offset = header_size;
+
+	// Bitfields follow a plain BITMAPINFOHEADER, later versions contain them.
+	let masks = match compression {
+		BI_RGB => match bit_count {
+			16 => Some(Masks::new(0x7c00, 0x03e0, 0x001f, 0)),
+			24 => Some(Masks::new(0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0)),
+			// The unused byte has no meaning officially, but often carries alpha.
+			32 => Some(Masks::new(
+				0x00ff_0000,
+				0x0000_ff00,
+				0x0000_00ff,
+				0xff00_0000,
+			)),
+			_ => None,
+		},
+		BI_BITFIELDS | BI_ALPHABITFIELDS => {
+			// BITMAPV3INFOHEADER and later contain an alpha mask.
+			let has_alpha = compression == BI_ALPHABITFIELDS || header_size >= 56;
+			if header_size == INFO_HEADER_SIZE {
+				offset += if has_alpha { 16 } else { 12 };
+			}
+
+			Some(Masks::new(
+				u32_at(bytes, 40)?,
+				u32_at(bytes, 44)?,
+				u32_at(bytes, 48)?,
+				if has_alpha { u32_at(bytes, 52)? } else { 0 },
+			))
+		}
+		_ => return None,
+	};
+
+	let palette = if bit_count <= 8 {
+		let max_colors = 1 << bit_count;
+		let count = match colors_used {
+			0 => max_colors,
+			count => count.min(max_colors),
+		};
+
+		let palette: Option<Vec<[u8; 4]>> = (0..count)
+			.map(|index| {
+				let entry = offset + index * palette_entry_size;
+				let bgr = bytes.get(entry..entry + 3)?;
+				Some([bgr[2], bgr[1], bgr[0], 255])
+			})
+			.collect();
+		offset += count * palette_entry_size;
+		palette?
+	} else {
+		Vec::new()
+	};
+
+	let stride = (width * bit_count as usize).div_ceil(32) * 4;
+	let pixels_size = stride.checked_mul(height)?;
+
+	let offset = match pixel_offset {
+		Some(pixel_offset) => pixel_offset,
+		// Some programs put the masks after a V5 header as well, which the size reveals.
+		None if compression == BI_BITFIELDS
+			&& header_size > INFO_HEADER_SIZE
+			&& size_image != 0
+			&& bytes.len() == offset + 12 + size_image =>
+		{
+			offset + 12
+		}
+		None => offset,
+	};
+	let pixels = bytes.get(offset..offset.checked_add(pixels_size)?)?;
+
+	let mut rgba = Vec::with_capacity(width * height * 4);
+	for y in 0..height {
+		let row = if top_down { y } else { height - 1 - y };
+		let row = &pixels[row * stride..(row + 1) * stride];
+
+		for x in 0..width {
+			let pixel = match bit_count {
+				1 | 4 | 8 => {
+					let bits = bit_count as usize;
+					let bit = x * bits;
+					let index = (row[bit / 8] as usize >> (8 - bits - bit % 8)) & ((1 << bits) - 1);
+					*palette.get(index).unwrap_or(&[0, 0, 0, 255])
+				}
+				16 => {
+					let value = u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32;
+					masks.as_ref()?.rgba(value)
+				}
+				24 => {
+					let value = u32::from_le_bytes([row[x * 3], row[x * 3 + 1], row[x * 3 + 2], 0]);
+					masks.as_ref()?.rgba(value)
+				}
+				32 => {
+					let value = u32_at(row, x * 4)?;
+					masks.as_ref()?.rgba(value)
+				}
+				_ => return None,
+			};
+
+			rgba.extend_from_slice(&pixel);
+		}
+	}
+
+	// Only the unused byte of BI_RGB may be premultiplied, an explicit alpha mask is straight.
+	fix_alpha(&mut rgba, compression == BI_RGB);
+
+	Some(ClipboardImage {
+		width: width as u32,
+		height: height as u32,
+		rgba,
+	})
+}
+
+/// Repairs alpha channels, which are present but not meant as straight alpha.
+///
+/// A channel of only zeros is treated as opaque, as many programs leave it unset. Without an alpha
+/// mask, a channel where no colour exceeds its alpha and some pixels are partly transparent is
+/// treated as premultiplied, which is what GDI expects and therefore what many programs write.
+fn fix_alpha(rgba: &mut [u8], maybe_premultiplied: bool) {
+	let pixels = || rgba.chunks_exact(4);
+
+	if pixels().all(|pixel| pixel[3] == 0) {
+		rgba.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 255);
+		return;
+	}
+
+	let premultiplied = maybe_premultiplied
+		&& pixels().all(|pixel| pixel[..3].iter().all(|channel| *channel <= pixel[3]))
+		&& pixels().any(|pixel| pixel[3] != 0 && pixel[3] != 255);
+	if !premultiplied {
+		return;
+	}
+
+	for pixel in rgba.chunks_exact_mut(4) {
+		let alpha = pixel[3] as u32;
+		if alpha == 0 {
+			continue;
+		}
+
+		for channel in &mut pixel[..3] {
+			*channel = ((*channel as u32 * 255 + alpha / 2) / alpha) as u8;
+		}
+	}
+}
+
+/// Encodes straight RGBA pixels as a bottom-up 32 bit DIB with a BITMAPV5HEADER.
+pub(crate) fn encode_dibv5(image: &ClipboardImage) -> Option<Vec<u8>> {
+	let width = image.width as usize;
+	let height = image.height as usize;
+	let size_image = width.checked_mul(height)?.checked_mul(4)?;
+	if size_image == 0
+		|| image.rgba.len() != size_image
+		|| i32::try_from(image.width).is_err()
+		|| i32::try_from(image.height).is_err()
+	{
+		return None;
+	}
+
+	let mut bytes = Vec::with_capacity(V5_HEADER_SIZE + size_image);
+	bytes.extend_from_slice(&(V5_HEADER_SIZE as u32).to_le_bytes());
+	bytes.extend_from_slice(&(image.width as i32).to_le_bytes());
+	bytes.extend_from_slice(&(image.height as i32).to_le_bytes());
+	bytes.extend_from_slice(&1u16.to_le_bytes());
+	bytes.extend_from_slice(&32u16.to_le_bytes());
+	bytes.extend_from_slice(&BI_BITFIELDS.to_le_bytes());
+	bytes.extend_from_slice(&(size_image as u32).to_le_bytes());
+	// Resolution, used and important colours.
+	bytes.extend_from_slice(&[0; 16]);
+	for mask in [0x00ff_0000u32, 0x0000_ff00, 0x0000_00ff, 0xff00_0000] {
+		bytes.extend_from_slice(&mask.to_le_bytes());
+	}
+	bytes.extend_from_slice(&LCS_SRGB.to_le_bytes());
+	// Endpoints and gamma, which are unused with sRGB.
+	bytes.extend_from_slice(&[0; 48]);
+	bytes.extend_from_slice(&LCS_GM_IMAGES.to_le_bytes());
+	// Profile data, profile size and reserved.
+	bytes.extend_from_slice(&[0; 12]);
+
+	for row in image.rgba.chunks_exact(width * 4).rev() {
+		for pixel in row.chunks_exact(4) {
+			bytes.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+		}
+	}
+
+	Some(bytes)
+}
+
+/// Encodes straight RGBA pixels as a BMP file, which is a DIB with a file header in front.
+pub(crate) fn encode_bmp(image: &ClipboardImage) -> Option<Vec<u8>> {
+	let dib = encode_dibv5(image)?;
+	let file_size = u32::try_from(FILE_HEADER_SIZE + dib.len()).ok()?;
+
+	let mut bytes = Vec::with_capacity(FILE_HEADER_SIZE + dib.len());
+	bytes.extend_from_slice(b"BM");
+	bytes.extend_from_slice(&file_size.to_le_bytes());
+	// Reserved.
+	bytes.extend_from_slice(&[0; 4]);
+	bytes.extend_from_slice(&((FILE_HEADER_SIZE + V5_HEADER_SIZE) as u32).to_le_bytes());
+	bytes.extend_from_slice(&dib);
+
+	Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn fixture(name: &str) -> Vec<u8> {
+		let path = format!("{}/tests/fixtures/dib/{name}", env!("CARGO_MANIFEST_DIR"));
+		std::fs::read(&path).unwrap_or_else(|error| panic!("Failed to read {path}: {error}"))
+	}
+
+	fn assert_image(image: Option<ClipboardImage>, width: u32, height: u32, rgba: &[u8]) {
+		let image = image.expect("Failed to decode the fixture");
+		assert_eq!((image.width, image.height), (width, height));
+		assert_eq!(image.rgba, rgba);
+	}
+
+	/// Red, green in the top row and blue, white in the bottom row.
+	const OPAQUE_2X2: [u8; 16] = [
+		255, 0, 0, 255, 0, 255, 0, 255, //
+		0, 0, 255, 255, 255, 255, 255, 255,
+	];
+
+	#[test]
+	fn decodes_24_bit_bottom_up() {
+		assert_image(decode(&fixture("rgb24_bottom_up.dib")), 2, 2, &OPAQUE_2X2);
+	}
+
+	#[test]
+	fn decodes_core_header() {
+		assert_image(decode(&fixture("core24.dib")), 2, 2, &OPAQUE_2X2);
+	}
+
+	#[test]
+	fn decodes_32_bit_without_alpha_as_opaque() {
+		assert_image(decode(&fixture("rgb32_zero_alpha.dib")), 2, 2, &OPAQUE_2X2);
+	}
+
+	#[test]
+	fn decodes_16_bit_565_bitfields() {
+		assert_image(decode(&fixture("bitfields565.dib")), 2, 2, &OPAQUE_2X2);
+	}
+
+	#[test]
+	fn decodes_palettes() {
+		assert_image(decode(&fixture("pal8.dib")), 2, 2, &OPAQUE_2X2);
+		assert_image(decode(&fixture("pal4.dib")), 2, 2, &OPAQUE_2X2);
+		assert_image(
+			decode(&fixture("pal1.dib")),
+			3,
+			1,
+			&[0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255],
+		);
+	}
+
+	#[test]
+	fn decodes_v5_top_down_with_alpha() {
+		assert_image(
+			decode(&fixture("v5_top_down_alpha.dib")),
+			2,
+			1,
+			&[255, 0, 0, 128, 0, 0, 255, 0],
+		);
+	}
+
+	#[test]
+	fn unpremultiplies_alpha() {
+		assert_image(
+			decode(&fixture("rgb32_premultiplied.dib")),
+			2,
+			1,
+			&[255, 0, 0, 128, 0, 255, 0, 255],
+		);
+	}
+
+	#[test]
+	fn decodes_bmp_file() {
+		assert_image(decode_bmp(&fixture("rgb24.bmp")), 2, 2, &OPAQUE_2X2);
+	}
+
+	#[test]
+	fn rejects_truncated_data() {
+		let bytes = fixture("rgb24_bottom_up.dib");
+		for length in 0..bytes.len() {
+			assert!(decode(&bytes[..length]).is_none());
+		}
+	}
+
+	#[test]
+	fn encodes_dibv5_round_trip() {
+		let image = ClipboardImage {
+			width: 2,
+			height: 2,
+			rgba: vec![
+				255, 0, 0, 255, 0, 255, 0, 64, //
+				0, 0, 255, 0, 10, 20, 30, 200,
+			],
+		};
+
+		let bytes = encode_dibv5(&image).unwrap();
+		assert_eq!(bytes.len(), V5_HEADER_SIZE + 16);
+		assert_eq!(decode(&bytes), Some(image.clone()));
+
+		let bytes = encode_bmp(&image).unwrap();
+		assert_eq!(u32_at(&bytes, 2), Some(bytes.len() as u32));
+		assert_eq!(decode_bmp(&bytes), Some(image));
+	}
+
+	#[test]
+	fn keeps_straight_alpha_with_alpha_mask() {
+		// No colour exceeds its alpha, which looks premultiplied without an alpha mask.
+		let image = ClipboardImage {
+			width: 2,
+			height: 1,
+			rgba: vec![100, 0, 0, 200, 10, 20, 30, 64],
+		};
+
+		assert_eq!(decode(&encode_dibv5(&image).unwrap()), Some(image));
+	}
+}
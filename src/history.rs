@@ -0,0 +1,308 @@
+//! Clipboard history, which records clipboard content as it changes.
+//!
+//! Wrap your [`ClipboardHandler`] with [`ClipboardHistory::handler`] and pass the result to
+//! [`Clipboard::new`]. Every clipboard change, and every paste, is then recorded as a
+//! [`ClipboardSnapshot`].
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{Clipboard, ClipboardEvent, ClipboardHandler, ClipboardSnapshot, DataAccess};
+
+/// Raw types stored by default, which are text, HTML and files.
+///
+/// Every clipboard change fetches the stored raw types right away, so large formats like images
+/// are left out unless asked for.
+const DEFAULT_FORMATS: [&str; 12] = [
+	"text/plain;charset=utf-8",
+	"UTF8_STRING",
+	"text/plain",
+	"STRING",
+	"CF_UNICODETEXT",
+	"text/html",
+	"HTML Format",
+	"x-special/gnome-copied-files",
+	"text/uri-list",
+	"application/x-kde-cutselection",
+	"CF_HDROP",
+	"Preferred DropEffect",
+];
+
+#[derive(Debug, Clone)]
+pub struct HistoryConfig {
+	/// Raw types, which are stored for each entry. `None` stores all offered raw types.
+	///
+	/// Defaults to text, HTML and files.
+	pub formats: Option<Vec<String>>,
+	/// Maximum number of entries, pinned entries included.
+	pub max_items: usize,
+	/// Maximum number of bytes stored over all entries, pinned entries included.
+	pub max_bytes: usize,
+}
+
+impl Default for HistoryConfig {
+	fn default() -> Self {
+		Self {
+			formats: Some(DEFAULT_FORMATS.map(str::to_string).to_vec()),
+			max_items: 50,
+			max_bytes: 64 * 1024 * 1024,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+	pub snapshot: ClipboardSnapshot,
+	/// Pinned entries are never evicted to stay within the budgets.
+	pub pinned: bool,
+}
+
+impl HistoryEntry {
+	fn size(&self) -> usize {
+		self.snapshot.entries().map(|(_, data)| data.len()).sum()
+	}
+}
+
+struct HistoryState {
+	config: HistoryConfig,
+	/// Newest entries come first.
+	entries: Vec<HistoryEntry>,
+}
+
+impl HistoryState {
+	fn insert(&mut self, snapshot: ClipboardSnapshot) {
+		let mut entry = HistoryEntry {
+			snapshot,
+			pinned: false,
+		};
+
+		if entry.size() > self.config.max_bytes {
+			return;
+		}
+
+		// Recording the same content again only moves it to the front.
+		if let Some(index) = self
+			.entries
+			.iter()
+			.position(|existing| same_content(&existing.snapshot, &entry.snapshot))
+		{
+			entry.pinned = self.entries.remove(index).pinned;
+		}
+
+		self.entries.insert(0, entry);
+		self.evict();
+	}
+
+	fn evict(&mut self) {
+		loop {
+			let size: usize = self.entries.iter().map(HistoryEntry::size).sum();
+			if self.entries.len() <= self.config.max_items && size <= self.config.max_bytes {
+				return;
+			}
+
+			match self.entries.iter().rposition(|entry| !entry.pinned) {
+				Some(index) => {
+					self.entries.remove(index);
+				}
+				None => return,
+			}
+		}
+	}
+}
+
+fn same_content(a: &ClipboardSnapshot, b: &ClipboardSnapshot) -> bool {
+	a.entries().eq(b.entries())
+}
+
+/// Shared clipboard history. Clones refer to the same history.
+#[derive(Clone)]
+pub struct ClipboardHistory {
+	state: Arc<Mutex<HistoryState>>,
+}
+
+impl ClipboardHistory {
+	pub fn new(config: HistoryConfig) -> Self {
+		let state = HistoryState {
+			config,
+			entries: Vec::new(),
+		};
+
+		Self {
+			state: Arc::new(Mutex::new(state)),
+		}
+	}
+
+	/// Wraps `handler`, so all clipboard content it sees is recorded in this history.
+	pub fn handler<T: ClipboardHandler>(&self, handler: T) -> HistoryHandler<T> {
+		HistoryHandler {
+			history: self.clone(),
+			handler,
+		}
+	}
+
+	fn state(&self) -> MutexGuard<'_, HistoryState> {
+		self.state.lock().unwrap_or_else(|error| error.into_inner())
+	}
+
+	/// Records the content of `data`, unless it is marked as sensitive.
+	pub fn record(&self, data: &DataAccess) {
+		if data.is_sensitive() {
+			return;
+		}
+
+		let snapshot = {
+			let state = self.state();
+			match state.config.formats.as_ref() {
+				Some(formats) => {
+					let formats: Vec<&str> = formats.iter().map(String::as_str).collect();
+					data.snapshot(Some(&formats))
+				}
+				None => data.snapshot(None),
+			}
+		};
+
+		if !snapshot.is_empty() {
+			self.state().insert(snapshot);
+		}
+	}
+
+	/// All entries, with the newest first.
+	pub fn entries(&self) -> Vec<HistoryEntry> {
+		self.state().entries.clone()
+	}
+
+	pub fn pin(&self, index: usize) -> bool {
+		self.set_pinned(index, true)
+	}
+
+	pub fn unpin(&self, index: usize) -> bool {
+		let unpinned = self.set_pinned(index, false);
+		self.state().evict();
+		unpinned
+	}
+
+	fn set_pinned(&self, index: usize, pinned: bool) -> bool {
+		match self.state().entries.get_mut(index) {
+			Some(entry) => {
+				entry.pinned = pinned;
+				true
+			}
+			None => false,
+		}
+	}
+
+	pub fn remove(&self, index: usize) -> Option<HistoryEntry> {
+		let mut state = self.state();
+		(index < state.entries.len()).then(|| state.entries.remove(index))
+	}
+
+	/// Writes the entry at `index` back to the system clipboard.
+	pub fn restore(&self, index: usize, clipboard: &Clipboard) -> bool {
+		let snapshot = match self.state().entries.get(index) {
+			Some(entry) => entry.snapshot.clone(),
+			None => return false,
+		};
+
+		clipboard.write_data(snapshot);
+		true
+	}
+}
+
+/// [`ClipboardHandler`] created by [`ClipboardHistory::handler`].
+pub struct HistoryHandler<T: ClipboardHandler> {
+	history: ClipboardHistory,
+	handler: T,
+}
+
+impl<T: ClipboardHandler> ClipboardHandler for HistoryHandler<T> {
+	fn handle_event(&mut self, event: ClipboardEvent<'_>) {
+		match &event {
+			ClipboardEvent::ClipboardChanged { data, .. }
+			| ClipboardEvent::PasteResult { data, .. } => self.history.record(data),
+			_ => {}
+		}
+
+		self.handler.handle_event(event);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn text(text: &str) -> ClipboardSnapshot {
+		ClipboardSnapshot::new(vec![(
+			"text/plain;charset=utf-8".to_string(),
+			text.as_bytes().to_vec(),
+		)])
+	}
+
+	fn history(max_items: usize) -> ClipboardHistory {
+		ClipboardHistory::new(HistoryConfig {
+			max_items,
+			..Default::default()
+		})
+	}
+
+	fn texts(history: &ClipboardHistory) -> Vec<String> {
+		history
+			.entries()
+			.iter()
+			.filter_map(|entry| entry.snapshot.read_data::<String>())
+			.collect()
+	}
+
+	#[test]
+	fn moves_duplicates_to_the_front() {
+		let history = history(10);
+		for content in ["a", "b", "a", "a"] {
+			history.record(&text(content).as_data_access());
+		}
+
+		assert_eq!(texts(&history), ["a", "b"]);
+	}
+
+	#[test]
+	fn evicts_oldest_unpinned_entries() {
+		let history = history(2);
+		for content in ["a", "b", "c"] {
+			history.record(&text(content).as_data_access());
+		}
+		assert_eq!(texts(&history), ["c", "b"]);
+
+		assert!(history.pin(1));
+		history.record(&text("d").as_data_access());
+		history.record(&text("e").as_data_access());
+		assert_eq!(texts(&history), ["e", "b"]);
+		assert!(history.entries()[1].pinned);
+
+		assert!(history.unpin(1));
+		history.record(&text("f").as_data_access());
+		assert_eq!(texts(&history), ["f", "e"]);
+		assert!(!history.pin(5));
+	}
+
+	#[test]
+	fn stores_default_formats_only() {
+		let history = history(10);
+		let snapshot = ClipboardSnapshot::new(vec![
+			("text/html".to_string(), b"<b>a</b>".to_vec()),
+			("image/png".to_string(), vec![0; 64]),
+		]);
+		history.record(&snapshot.as_data_access());
+
+		let entries = history.entries();
+		assert_eq!(entries[0].snapshot.raw_types(), ["text/html"]);
+	}
+
+	#[test]
+	fn skips_sensitive_content() {
+		let history = history(10);
+		let snapshot = ClipboardSnapshot::new(vec![
+			("text/plain".to_string(), b"hunter2".to_vec()),
+			("x-kde-passwordManagerHint".to_string(), b"secret".to_vec()),
+		]);
+		history.record(&snapshot.as_data_access());
+
+		assert!(history.entries().is_empty());
+	}
+}
@@ -0,0 +1,207 @@
+use std::sync::OnceLock;
+
+use super::{files, html, image, text};
+use crate::{ClipboardFiles, ClipboardHtml, ClipboardImage, WasmOrSend, WriteToClipboard};
+
+/// Creates the bytes of a format, when a paste target asks for it.
+trait Producer: WasmOrSend + 'static {
+	fn produce(&self, mime_type: &str) -> Option<Vec<u8>>;
+}
+
+impl<F: Fn(&str) -> Option<Vec<u8>> + WasmOrSend + 'static> Producer for F {
+	fn produce(&self, mime_type: &str) -> Option<Vec<u8>> {
+		self(mime_type)
+	}
+}
+
+struct Representation {
+	mime_types: Vec<String>,
+	producer: Box<dyn Producer>,
+}
+
+/// Content with several representations, which are written together by a single
+/// [`crate::Clipboard::write_data`].
+///
+/// ```ignore
+/// let content = ClipboardContentBuilder::new()
+///     .html(ClipboardHtml::new("<b>Boss</b> defeated".to_string()))
+///     .image_with(|| take_screenshot())
+///     .custom("application/x-gengine-replay", replay_bytes);
+/// clipboard.write_data(content);
+/// ```
+///
+/// When several representations offer the same raw type, the first one added wins. So plain
+/// text added before HTML replaces the text rendered from the HTML.
+///
+/// The `_with` variants take closures, which only run once a paste target asks for one of their
+/// formats. Their result is kept for the other formats of the representation.
+#[derive(Default)]
+pub struct ClipboardContentBuilder {
+	representations: Vec<Representation>,
+}
+
+impl ClipboardContentBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds any writable type, like the ones of the other methods.
+	pub fn with<T: WriteToClipboard>(mut self, data: T) -> Self {
+		self.representations.push(Representation {
+			mime_types: data.viable_conversions(),
+			producer: Box::new(move |mime_type: &str| data.convert_to(mime_type)),
+		});
+		self
+	}
+
+	fn lazy<T: WriteToClipboard>(
+		mut self,
+		mime_types: Vec<String>,
+		create: impl Fn() -> T + WasmOrSend + 'static,
+	) -> Self {
+		let value = OnceLock::new();
+		self.representations.push(Representation {
+			mime_types,
+			producer: Box::new(move |mime_type: &str| {
+				value.get_or_init(&create).convert_to(mime_type)
+			}),
+		});
+		self
+	}
+
+	pub fn text(self, text: impl Into<String>) -> Self {
+		self.with(text.into())
+	}
+
+	pub fn text_with(self, create: impl Fn() -> String + WasmOrSend + 'static) -> Self {
+		self.lazy(text::write_types(), create)
+	}
+
+	/// Adds HTML, together with its plain text rendering.
+	pub fn html(self, html: ClipboardHtml) -> Self {
+		self.with(html)
+	}
+
+	pub fn html_with(self, create: impl Fn() -> ClipboardHtml + WasmOrSend + 'static) -> Self {
+		self.lazy(html::write_types(), create)
+	}
+
+	pub fn image(self, image: ClipboardImage) -> Self {
+		self.with(image)
+	}
+
+	pub fn image_with(self, create: impl Fn() -> ClipboardImage + WasmOrSend + 'static) -> Self {
+		self.lazy(image::write_types(), create)
+	}
+
+	pub fn files(self, files: ClipboardFiles) -> Self {
+		self.with(files)
+	}
+
+	pub fn files_with(self, create: impl Fn() -> ClipboardFiles + WasmOrSend + 'static) -> Self {
+		self.lazy(files::write_types(), create)
+	}
+
+	/// Adds the raw bytes of an application specific format.
+	pub fn custom(mut self, mime_type: impl Into<String>, bytes: Vec<u8>) -> Self {
+		self.representations.push(Representation {
+			mime_types: vec![mime_type.into()],
+			producer: Box::new(move |_: &str| Some(bytes.clone())),
+		});
+		self
+	}
+
+	/// Adds an application specific format, whose bytes are created by `create` when they are
+	/// asked for. Unlike the other `_with` methods, `create` runs on every request.
+	pub fn custom_with(
+		mut self,
+		mime_type: impl Into<String>,
+		create: impl Fn() -> Vec<u8> + WasmOrSend + 'static,
+	) -> Self {
+		self.representations.push(Representation {
+			mime_types: vec![mime_type.into()],
+			producer: Box::new(move |_: &str| Some(create())),
+		});
+		self
+	}
+
+	fn find(&self, mime_type: &str) -> Option<&Representation> {
+		self.representations.iter().find(|representation| {
+			representation
+				.mime_types
+				.iter()
+				.any(|offered| offered == mime_type)
+		})
+	}
+}
+
+impl WriteToClipboard for ClipboardContentBuilder {
+	fn viable_conversions(&self) -> Vec<String> {
+		let mut conversions: Vec<String> = Vec::new();
+		for mime_type in self
+			.representations
+			.iter()
+			.flat_map(|representation| &representation.mime_types)
+		{
+			if !conversions.contains(mime_type) {
+				conversions.push(mime_type.clone());
+			}
+		}
+
+		conversions
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		self.find(mime_type)?.producer.produce(mime_type)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{
+		Arc,
+		atomic::{AtomicUsize, Ordering},
+	};
+
+	use super::*;
+
+	#[test]
+	fn first_representation_wins() {
+		let content = ClipboardContentBuilder::new()
+			.text("plain")
+			.html(ClipboardHtml::new("<b>rich</b>".to_string()))
+			.custom("application/x-test", vec![1, 2]);
+
+		let conversions = content.viable_conversions();
+		let text_type = &text::write_types()[0];
+		assert_eq!(
+			conversions
+				.iter()
+				.filter(|conversion| *conversion == text_type)
+				.count(),
+			1
+		);
+		assert_eq!(
+			content.convert_to(text_type),
+			text::encode_text(text_type, "plain")
+		);
+		assert_eq!(content.convert_to("application/x-test"), Some(vec![1, 2]));
+		assert_eq!(content.convert_to("application/x-missing"), None);
+	}
+
+	#[test]
+	fn creates_lazily_once() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let counter = calls.clone();
+		let content = ClipboardContentBuilder::new().text_with(move || {
+			counter.fetch_add(1, Ordering::Relaxed);
+			"lazy".to_string()
+		});
+
+		assert_eq!(calls.load(Ordering::Relaxed), 0);
+		for mime_type in content.viable_conversions() {
+			assert!(content.convert_to(&mime_type).is_some());
+		}
+		assert_eq!(calls.load(Ordering::Relaxed), 1);
+	}
+}
@@ -0,0 +1,161 @@
+//! Single byte Windows code pages, as RTF documents declare them with `\ansicpg`.
+
+use super::text::windows_1252;
+
+/// Thai, bytes from 0x80 on.
+const CP874_HIGH: [char; 128] = [
+	'€', '\u{81}', '\u{82}', '\u{83}', '\u{84}', '…', '\u{86}', '\u{87}', '\u{88}', '\u{89}',
+	'\u{8a}', '\u{8b}', '\u{8c}', '\u{8d}', '\u{8e}', '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•',
+	'–', '—', '\u{98}', '\u{99}', '\u{9a}', '\u{9b}', '\u{9c}', '\u{9d}', '\u{9e}', '\u{9f}',
+	'\u{a0}', 'ก', 'ข', 'ฃ', 'ค', 'ฅ', 'ฆ', 'ง', 'จ', 'ฉ', 'ช', 'ซ', 'ฌ', 'ญ', 'ฎ', 'ฏ', 'ฐ', 'ฑ',
+	'ฒ', 'ณ', 'ด', 'ต', 'ถ', 'ท', 'ธ', 'น', 'บ', 'ป', 'ผ', 'ฝ', 'พ', 'ฟ', 'ภ', 'ม', 'ย', 'ร', 'ฤ',
+	'ล', 'ฦ', 'ว', 'ศ', 'ษ', 'ส', 'ห', 'ฬ', 'อ', 'ฮ', 'ฯ', 'ะ', '\u{e31}', 'า', 'ำ', '\u{e34}',
+	'\u{e35}', '\u{e36}', '\u{e37}', '\u{e38}', '\u{e39}', '\u{e3a}', 'Û', 'Ü', 'Ý', 'Þ', '฿', 'เ',
+	'แ', 'โ', 'ใ', 'ไ', 'ๅ', 'ๆ', '\u{e47}', '\u{e48}', '\u{e49}', '\u{e4a}', '\u{e4b}', '\u{e4c}',
+	'\u{e4d}', '\u{e4e}', '๏', '๐', '๑', '๒', '๓', '๔', '๕', '๖', '๗', '๘', '๙', '๚', '๛', 'ü',
+	'ý', 'þ', 'ÿ',
+];
+
+/// Central European, bytes from 0x80 on.
+const CP1250_HIGH: [char; 128] = [
+	'€', '\u{81}', '‚', '\u{83}', '„', '…', '†', '‡', '\u{88}', '‰', 'Š', '‹', 'Ś', 'Ť', 'Ž', 'Ź',
+	'\u{90}', '‘', '’', '“', '”', '•', '–', '—', '\u{98}', '™', 'š', '›', 'ś', 'ť', 'ž', 'ź',
+	'\u{a0}', 'ˇ', '˘', 'Ł', '¤', 'Ą', '¦', '§', '¨', '©', 'Ş', '«', '¬', '\u{ad}', '®', 'Ż', '°',
+	'±', '˛', 'ł', '´', 'µ', '¶', '·', '¸', 'ą', 'ş', '»', 'Ľ', '˝', 'ľ', 'ż', 'Ŕ', 'Á', 'Â', 'Ă',
+	'Ä', 'Ĺ', 'Ć', 'Ç', 'Č', 'É', 'Ę', 'Ë', 'Ě', 'Í', 'Î', 'Ď', 'Đ', 'Ń', 'Ň', 'Ó', 'Ô', 'Ő', 'Ö',
+	'×', 'Ř', 'Ů', 'Ú', 'Ű', 'Ü', 'Ý', 'Ţ', 'ß', 'ŕ', 'á', 'â', 'ă', 'ä', 'ĺ', 'ć', 'ç', 'č', 'é',
+	'ę', 'ë', 'ě', 'í', 'î', 'ď', 'đ', 'ń', 'ň', 'ó', 'ô', 'ő', 'ö', '÷', 'ř', 'ů', 'ú', 'ű', 'ü',
+	'ý', 'ţ', '˙',
+];
+
+/// Cyrillic, bytes from 0x80 on.
+const CP1251_HIGH: [char; 128] = [
+	'Ђ', 'Ѓ', '‚', 'ѓ', '„', '…', '†', '‡', '€', '‰', 'Љ', '‹', 'Њ', 'Ќ', 'Ћ', 'Џ', 'ђ', '‘', '’',
+	'“', '”', '•', '–', '—', '\u{98}', '™', 'љ', '›', 'њ', 'ќ', 'ћ', 'џ', '\u{a0}', 'Ў', 'ў', 'Ј',
+	'¤', 'Ґ', '¦', '§', 'Ё', '©', 'Є', '«', '¬', '\u{ad}', '®', 'Ї', '°', '±', 'І', 'і', 'ґ', 'µ',
+	'¶', '·', 'ё', '№', 'є', '»', 'ј', 'Ѕ', 'ѕ', 'ї', 'А', 'Б', 'В', 'Г', 'Д', 'Е', 'Ж', 'З', 'И',
+	'Й', 'К', 'Л', 'М', 'Н', 'О', 'П', 'Р', 'С', 'Т', 'У', 'Ф', 'Х', 'Ц', 'Ч', 'Ш', 'Щ', 'Ъ', 'Ы',
+	'Ь', 'Э', 'Ю', 'Я', 'а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о',
+	'п', 'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+];
+
+/// Greek, bytes from 0x80 on.
+const CP1253_HIGH: [char; 128] = [
+	'€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', '\u{88}', '‰', '\u{8a}', '‹', '\u{8c}', '\u{8d}',
+	'\u{8e}', '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '\u{98}', '™', '\u{9a}', '›',
+	'\u{9c}', '\u{9d}', '\u{9e}', '\u{9f}', '\u{a0}', '΅', 'Ά', '£', '¤', '¥', '¦', '§', '¨', '©',
+	'ª', '«', '¬', '\u{ad}', '®', '―', '°', '±', '²', '³', '΄', 'µ', '¶', '·', 'Έ', 'Ή', 'Ί', '»',
+	'Ό', '½', 'Ύ', 'Ώ', 'ΐ', 'Α', 'Β', 'Γ', 'Δ', 'Ε', 'Ζ', 'Η', 'Θ', 'Ι', 'Κ', 'Λ', 'Μ', 'Ν', 'Ξ',
+	'Ο', 'Π', 'Ρ', 'Ò', 'Σ', 'Τ', 'Υ', 'Φ', 'Χ', 'Ψ', 'Ω', 'Ϊ', 'Ϋ', 'ά', 'έ', 'ή', 'ί', 'ΰ', 'α',
+	'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'λ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ', 'ς', 'σ', 'τ',
+	'υ', 'φ', 'χ', 'ψ', 'ω', 'ϊ', 'ϋ', 'ό', 'ύ', 'ώ', 'ÿ',
+];
+
+/// Turkish, bytes from 0x80 on.
+const CP1254_HIGH: [char; 128] = [
+	'€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', '\u{8e}',
+	'\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+	'\u{9e}', 'Ÿ', '\u{a0}', '¡', '¢', '£', '¤', '¥', '¦', '§', '¨', '©', 'ª', '«', '¬', '\u{ad}',
+	'®', '¯', '°', '±', '²', '³', '´', 'µ', '¶', '·', '¸', '¹', 'º', '»', '¼', '½', '¾', '¿', 'À',
+	'Á', 'Â', 'Ã', 'Ä', 'Å', 'Æ', 'Ç', 'È', 'É', 'Ê', 'Ë', 'Ì', 'Í', 'Î', 'Ï', 'Ğ', 'Ñ', 'Ò', 'Ó',
+	'Ô', 'Õ', 'Ö', '×', 'Ø', 'Ù', 'Ú', 'Û', 'Ü', 'İ', 'Ş', 'ß', 'à', 'á', 'â', 'ã', 'ä', 'å', 'æ',
+	'ç', 'è', 'é', 'ê', 'ë', 'ì', 'í', 'î', 'ï', 'ğ', 'ñ', 'ò', 'ó', 'ô', 'õ', 'ö', '÷', 'ø', 'ù',
+	'ú', 'û', 'ü', 'ı', 'ş', 'ÿ',
+];
+
+/// Hebrew, bytes from 0x80 on.
+const CP1255_HIGH: [char; 128] = [
+	'€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', '\u{8a}', '‹', '\u{8c}', '\u{8d}',
+	'\u{8e}', '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', '\u{9a}', '›',
+	'\u{9c}', '\u{9d}', '\u{9e}', '\u{9f}', '\u{a0}', '¡', '¢', '£', '₪', '¥', '¦', '§', '¨', '©',
+	'×', '«', '¬', '\u{ad}', '®', '¯', '°', '±', '²', '³', '´', 'µ', '¶', '·', '¸', '¹', '÷', '»',
+	'¼', '½', '¾', '¿', '\u{5b0}', '\u{5b1}', '\u{5b2}', '\u{5b3}', '\u{5b4}', '\u{5b5}',
+	'\u{5b6}', '\u{5b7}', '\u{5b8}', '\u{5b9}', 'Ê', '\u{5bb}', '\u{5bc}', '\u{5bd}', '־',
+	'\u{5bf}', '׀', '\u{5c1}', '\u{5c2}', '׃', 'װ', 'ױ', 'ײ', '׳', '״', 'Ù', 'Ú', 'Û', 'Ü', 'Ý',
+	'Þ', 'ß', 'א', 'ב', 'ג', 'ד', 'ה', 'ו', 'ז', 'ח', 'ט', 'י', 'ך', 'כ', 'ל', 'ם', 'מ', 'ן', 'נ',
+	'ס', 'ע', 'ף', 'פ', 'ץ', 'צ', 'ק', 'ר', 'ש', 'ת', 'û', 'ü', '\u{200e}', '\u{200f}', 'ÿ',
+];
+
+/// Arabic, bytes from 0x80 on.
+const CP1256_HIGH: [char; 128] = [
+	'€', 'پ', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'ٹ', '‹', 'Œ', 'چ', 'ژ', 'ڈ', 'گ', '‘', '’',
+	'“', '”', '•', '–', '—', 'ک', '™', 'ڑ', '›', 'œ', '\u{200c}', '\u{200d}', 'ں', '\u{a0}', '،',
+	'¢', '£', '¤', '¥', '¦', '§', '¨', '©', 'ھ', '«', '¬', '\u{ad}', '®', '¯', '°', '±', '²', '³',
+	'´', 'µ', '¶', '·', '¸', '¹', '؛', '»', '¼', '½', '¾', '؟', 'ہ', 'ء', 'آ', 'أ', 'ؤ', 'إ', 'ئ',
+	'ا', 'ب', 'ة', 'ت', 'ث', 'ج', 'ح', 'خ', 'د', 'ذ', 'ر', 'ز', 'س', 'ش', 'ص', 'ض', '×', 'ط', 'ظ',
+	'ع', 'غ', 'ـ', 'ف', 'ق', 'ك', 'à', 'ل', 'â', 'م', 'ن', 'ه', 'و', 'ç', 'è', 'é', 'ê', 'ë', 'ى',
+	'ي', 'î', 'ï', '\u{64b}', '\u{64c}', '\u{64d}', '\u{64e}', 'ô', '\u{64f}', '\u{650}', '÷',
+	'\u{651}', 'ù', '\u{652}', 'û', 'ü', '\u{200e}', '\u{200f}', 'ے',
+];
+
+/// Baltic, bytes from 0x80 on.
+const CP1257_HIGH: [char; 128] = [
+	'€', '\u{81}', '‚', '\u{83}', '„', '…', '†', '‡', '\u{88}', '‰', '\u{8a}', '‹', '\u{8c}', '¨',
+	'ˇ', '¸', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '\u{98}', '™', '\u{9a}', '›', '\u{9c}',
+	'¯', '˛', '\u{9f}', '\u{a0}', '¡', '¢', '£', '¤', '¥', '¦', '§', 'Ø', '©', 'Ŗ', '«', '¬',
+	'\u{ad}', '®', 'Æ', '°', '±', '²', '³', '´', 'µ', '¶', '·', 'ø', '¹', 'ŗ', '»', '¼', '½', '¾',
+	'æ', 'Ą', 'Į', 'Ā', 'Ć', 'Ä', 'Å', 'Ę', 'Ē', 'Č', 'É', 'Ź', 'Ė', 'Ģ', 'Ķ', 'Ī', 'Ļ', 'Š', 'Ń',
+	'Ņ', 'Ó', 'Ō', 'Õ', 'Ö', '×', 'Ų', 'Ł', 'Ś', 'Ū', 'Ü', 'Ż', 'Ž', 'ß', 'ą', 'į', 'ā', 'ć', 'ä',
+	'å', 'ę', 'ē', 'č', 'é', 'ź', 'ė', 'ģ', 'ķ', 'ī', 'ļ', 'š', 'ń', 'ņ', 'ó', 'ō', 'õ', 'ö', '÷',
+	'ų', 'ł', 'ś', 'ū', 'ü', 'ż', 'ž', '˙',
+];
+
+/// Vietnamese, bytes from 0x80 on.
+const CP1258_HIGH: [char; 128] = [
+	'€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', '\u{8a}', '‹', 'Œ', '\u{8d}', '\u{8e}',
+	'\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', '\u{9a}', '›', 'œ', '\u{9d}',
+	'\u{9e}', 'Ÿ', '\u{a0}', '¡', '¢', '£', '¤', '¥', '¦', '§', '¨', '©', 'ª', '«', '¬', '\u{ad}',
+	'®', '¯', '°', '±', '²', '³', '´', 'µ', '¶', '·', '¸', '¹', 'º', '»', '¼', '½', '¾', '¿', 'À',
+	'Á', 'Â', 'Ă', 'Ä', 'Å', 'Æ', 'Ç', 'È', 'É', 'Ê', 'Ë', '\u{300}', 'Í', 'Î', 'Ï', 'Đ', 'Ñ',
+	'\u{309}', 'Ó', 'Ô', 'Ơ', 'Ö', '×', 'Ø', 'Ù', 'Ú', 'Û', 'Ü', 'Ư', '\u{303}', 'ß', 'à', 'á',
+	'â', 'ă', 'ä', 'å', 'æ', 'ç', 'è', 'é', 'ê', 'ë', '\u{301}', 'í', 'î', 'ï', 'đ', 'ñ',
+	'\u{323}', 'ó', 'ô', 'ơ', 'ö', '÷', 'ø', 'ù', 'ú', 'û', 'ü', 'ư', '₫', 'ÿ',
+];
+
+/// Decodes a byte of `code_page`, or returns `None` for unknown and multi byte code pages.
+///
+/// Bytes, which are undefined in a code page, map to the code point of the same value, as Windows
+/// does.
+pub(super) fn decode_byte(code_page: i32, byte: u8) -> Option<char> {
+	let high = match code_page {
+		1252 => return Some(windows_1252(byte)),
+		874 => &CP874_HIGH,
+		1250 => &CP1250_HIGH,
+		1251 => &CP1251_HIGH,
+		1253 => &CP1253_HIGH,
+		1254 => &CP1254_HIGH,
+		1255 => &CP1255_HIGH,
+		1256 => &CP1256_HIGH,
+		1257 => &CP1257_HIGH,
+		1258 => &CP1258_HIGH,
+		_ => return None,
+	};
+
+	Some(match byte {
+		0x80..=0xff => high[byte as usize - 0x80],
+		byte => byte as char,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_single_byte_pages() {
+		let decode = |code_page, bytes: &[u8]| -> Option<String> {
+			bytes
+				.iter()
+				.map(|byte| decode_byte(code_page, *byte))
+				.collect()
+		};
+
+		assert_eq!(
+			decode(1251, b"\xcf\xf0\xe8\xe2\xe5\xf2").as_deref(),
+			Some("Привет")
+		);
+		assert_eq!(decode(1250, b"\x8a\xe8").as_deref(), Some("Šč"));
+		assert_eq!(decode(1253, b"\xe1\x80").as_deref(), Some("α€"));
+		assert_eq!(decode(932, b"a"), None);
+	}
+}
@@ -0,0 +1,279 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::text;
+use crate::{DataAccess, WasmOrSend, WriteToClipboard};
+
+/// How the values of a [`CustomFormat`] are turned into bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+	Json,
+	/// Needs the `bincode` feature.
+	#[cfg(feature = "bincode")]
+	Bincode,
+}
+
+/// Reads the value of an older or newer version from its raw bytes.
+pub type Migration<T> = fn(version: u32, bytes: &[u8]) -> Option<T>;
+
+/// A private format of the application, named `application/x-<app>-<type>;version=<N>`.
+///
+/// ```ignore
+/// static PREFAB: LazyLock<CustomFormat<Prefab>> = LazyLock::new(|| {
+///     CustomFormat::new("gengine", "prefab", 2, Codec::Json)
+///         .with_migration(prefab_from_v1)
+///         .with_text_fallback(|prefab| prefab.name.clone())
+/// });
+///
+/// clipboard.write_data(PREFAB.data(prefab));
+/// let prefab = PREFAB.read(data);
+/// ```
+///
+/// Content of another version is only read with a migration, as the layout may have changed.
+pub struct CustomFormat<T> {
+	mime_type: String,
+	/// The mime type without the version.
+	essence: String,
+	version: u32,
+	codec: Codec,
+	migration: Option<Migration<T>>,
+	text_fallback: Option<fn(&T) -> String>,
+}
+
+impl<T> Clone for CustomFormat<T> {
+	fn clone(&self) -> Self {
+		Self {
+			mime_type: self.mime_type.clone(),
+			essence: self.essence.clone(),
+			version: self.version,
+			codec: self.codec,
+			migration: self.migration,
+			text_fallback: self.text_fallback,
+		}
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> CustomFormat<T> {
+	pub fn new(app: &str, type_name: &str, version: u32, codec: Codec) -> Self {
+		let essence = format!("application/x-{app}-{type_name}").to_ascii_lowercase();
+		Self {
+			mime_type: format!("{essence};version={version}"),
+			essence,
+			version,
+			codec,
+			migration: None,
+			text_fallback: None,
+		}
+	}
+
+	/// Reads content of other versions with `migration`, instead of ignoring it.
+	pub fn with_migration(mut self, migration: Migration<T>) -> Self {
+		self.migration = Some(migration);
+		self
+	}
+
+	/// Also writes the value as text, for programs which do not know the format.
+	pub fn with_text_fallback(mut self, text_fallback: fn(&T) -> String) -> Self {
+		self.text_fallback = Some(text_fallback);
+		self
+	}
+
+	pub fn mime_type(&self) -> &str {
+		&self.mime_type
+	}
+
+	pub fn version(&self) -> u32 {
+		self.version
+	}
+
+	/// Returns the version of `raw_type`, if it is a version of this format.
+	fn version_of(&self, raw_type: &str) -> Option<u32> {
+		let mut parts = raw_type.split(';');
+		if !parts.next()?.trim().eq_ignore_ascii_case(&self.essence) {
+			return None;
+		}
+
+		parts.find_map(|parameter| {
+			let (name, value) = parameter.split_once('=')?;
+			match name.trim() {
+				"version" => value.trim().parse().ok(),
+				_ => None,
+			}
+		})
+	}
+
+	fn readable(&self, raw_type: &str) -> bool {
+		self.version_of(raw_type)
+			.is_some_and(|version| version == self.version || self.migration.is_some())
+	}
+
+	pub fn encode(&self, value: &T) -> Option<Vec<u8>> {
+		let result = match self.codec {
+			Codec::Json => serde_json::to_vec(value).map_err(|error| error.to_string()),
+			#[cfg(feature = "bincode")]
+			Codec::Bincode => bincode::serialize(value).map_err(|error| error.to_string()),
+		};
+
+		result
+			.inspect_err(|error| log::error!("Failed to encode {}: {error}", self.mime_type))
+			.ok()
+	}
+
+	/// Decodes bytes of this version, or migrates bytes of another one.
+	pub fn decode(&self, version: u32, bytes: &[u8]) -> Option<T> {
+		if version != self.version {
+			let Some(migration) = self.migration else {
+				log::warn!(
+					"Ignoring version {version} of {}, which has no migration.",
+					self.essence
+				);
+				return None;
+			};
+
+			return migration(version, bytes);
+		}
+
+		let result = match self.codec {
+			Codec::Json => serde_json::from_slice(bytes).map_err(|error| error.to_string()),
+			#[cfg(feature = "bincode")]
+			Codec::Bincode => bincode::deserialize(bytes).map_err(|error| error.to_string()),
+		};
+
+		result
+			.inspect_err(|error| log::warn!("Failed to decode {}: {error}", self.mime_type))
+			.ok()
+	}
+
+	/// Whether one of `mime_types` is this version, or another one with a migration.
+	pub fn is_available(&self, mime_types: &[&str]) -> bool {
+		mime_types.iter().any(|mime_type| self.readable(mime_type))
+	}
+
+	/// Reads the value, preferring the current version over the ones needing a migration.
+	pub fn read(&self, data: &DataAccess) -> Option<T> {
+		let available = data.raw_types();
+		let current = available
+			.iter()
+			.filter(|raw_type| self.version_of(raw_type) == Some(self.version));
+		let others = available
+			.iter()
+			.filter(|raw_type| self.version_of(raw_type) != Some(self.version));
+
+		current
+			.chain(others)
+			.filter(|raw_type| self.readable(raw_type))
+			.find_map(|raw_type| {
+				let bytes = data.get_raw_data(raw_type).ok()?;
+				self.decode(self.version_of(raw_type)?, &bytes)
+			})
+	}
+
+	/// Wraps `value`, so it can be written in this format.
+	pub fn data(&self, value: T) -> CustomData<T> {
+		CustomData {
+			format: self.clone(),
+			value,
+		}
+	}
+}
+
+/// A value to write in a [`CustomFormat`].
+pub struct CustomData<T> {
+	format: CustomFormat<T>,
+	value: T,
+}
+
+impl<T: Serialize + DeserializeOwned + WasmOrSend + 'static> WriteToClipboard for CustomData<T> {
+	fn viable_conversions(&self) -> Vec<String> {
+		let mut conversions = vec![self.format.mime_type.clone()];
+		if self.format.text_fallback.is_some() {
+			conversions.extend(text::write_types());
+		}
+		conversions
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		if mime_type == self.format.mime_type {
+			return self.format.encode(&self.value);
+		}
+
+		let text = (self.format.text_fallback?)(&self.value);
+		text::encode_text(mime_type, &text)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde::Deserialize;
+
+	use super::*;
+	use crate::ClipboardSnapshot;
+
+	#[derive(Debug, PartialEq, Serialize, Deserialize)]
+	struct Prefab {
+		name: String,
+		health: u32,
+	}
+
+	fn prefab() -> Prefab {
+		Prefab {
+			name: "crate".to_string(),
+			health: 40,
+		}
+	}
+
+	fn write(format: &CustomFormat<Prefab>) -> ClipboardSnapshot {
+		let data = format.data(prefab());
+		let entries = data
+			.viable_conversions()
+			.into_iter()
+			.map(|mime_type| {
+				let bytes = data.convert_to(&mime_type).unwrap();
+				(mime_type, bytes)
+			})
+			.collect();
+		ClipboardSnapshot::new(entries)
+	}
+
+	#[test]
+	fn round_trips_with_text_fallback() {
+		let format = CustomFormat::new("gengine", "prefab", 2, Codec::Json)
+			.with_text_fallback(|prefab: &Prefab| prefab.name.clone());
+		assert_eq!(format.mime_type(), "application/x-gengine-prefab;version=2");
+
+		let snapshot = write(&format);
+		assert_eq!(format.read(&snapshot.as_data_access()), Some(prefab()));
+		assert_eq!(snapshot.read_data::<String>().as_deref(), Some("crate"));
+	}
+
+	#[test]
+	fn rejects_or_migrates_other_versions() {
+		let old = CustomFormat::<Prefab>::new("gengine", "prefab", 1, Codec::Json);
+		let snapshot = write(&old);
+
+		let new = CustomFormat::<Prefab>::new("gengine", "prefab", 2, Codec::Json);
+		assert!(!new.is_available(&snapshot.raw_types()));
+		assert_eq!(new.read(&snapshot.as_data_access()), None);
+
+		let new = new.with_migration(|version, bytes| {
+			assert_eq!(version, 1);
+			let mut prefab: Prefab = serde_json::from_slice(bytes).ok()?;
+			prefab.health *= 2;
+			Some(prefab)
+		});
+		assert!(new.is_available(&snapshot.raw_types()));
+		assert_eq!(
+			new.read(&snapshot.as_data_access())
+				.map(|prefab| prefab.health),
+			Some(80)
+		);
+	}
+
+	#[cfg(feature = "bincode")]
+	#[test]
+	fn round_trips_bincode() {
+		let format = CustomFormat::new("gengine", "prefab", 1, Codec::Bincode);
+		let bytes = format.encode(&prefab()).unwrap();
+		assert_eq!(format.decode(1, &bytes), Some(prefab()));
+		assert_eq!(format.decode(1, &bytes[..3]), None);
+	}
+}
@@ -0,0 +1,428 @@
+use std::path::{Path, PathBuf};
+
+use super::text::utf16le;
+use crate::{DataAccess, ReadFromClipboard, WriteToClipboard};
+
+const GNOME_COPIED_FILES: &str = "x-special/gnome-copied-files";
+const URI_LIST: &str = "text/uri-list";
+const KDE_CUT_SELECTION: &str = "application/x-kde-cutselection";
+const CF_HDROP: &str = "CF_HDROP";
+const PREFERRED_DROP_EFFECT: &str = "Preferred DropEffect";
+
+/// Size of the DROPFILES struct, which comes before the file names in `CF_HDROP`.
+const DROPFILES_SIZE: usize = 20;
+const DROPEFFECT_COPY: u32 = 1;
+const DROPEFFECT_MOVE: u32 = 2;
+
+/// Raw types files are written as.
+#[cfg(target_os = "windows")]
+const WRITE_TYPES: [&str; 2] = [CF_HDROP, PREFERRED_DROP_EFFECT];
+
+#[cfg(not(target_os = "windows"))]
+const WRITE_TYPES: [&str; 3] = [GNOME_COPIED_FILES, URI_LIST, KDE_CUT_SELECTION];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileOperation {
+	Copy,
+	/// The source expects the files to be moved, and removes them once they were pasted.
+	Cut,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardFiles {
+	pub paths: Vec<PathBuf>,
+	pub operation: FileOperation,
+}
+
+/// Parses `x-special/gnome-copied-files`, which is the operation followed by a URI per line.
+fn parse_gnome_copied_files(bytes: &[u8]) -> Option<ClipboardFiles> {
+	let text = String::from_utf8_lossy(bytes);
+	let mut lines = text.lines();
+	let operation = match lines.next()?.trim() {
+		"copy" => FileOperation::Copy,
+		"cut" => FileOperation::Cut,
+		_ => return None,
+	};
+
+	let paths = lines
+		.filter_map(|line| file_uri_to_path(line.trim()))
+		.collect();
+	Some(ClipboardFiles { paths, operation })
+}
+
+/// Parses `text/uri-list` as in RFC 2483. URIs, which are not local files, are left out.
+fn parse_uri_list(bytes: &[u8]) -> Vec<PathBuf> {
+	String::from_utf8_lossy(bytes)
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.filter_map(file_uri_to_path)
+		.collect()
+}
+
+fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+	let rest = uri.strip_prefix("file://")?;
+	// Only files on this machine can be used.
+	let path = match rest.find('/') {
+		Some(0) => rest,
+		Some(index) if &rest[..index] == "localhost" => &rest[index..],
+		_ => return None,
+	};
+
+	let bytes = percent_decode(path);
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::ffi::OsStringExt;
+		Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+	}
+
+	#[cfg(not(unix))]
+	{
+		let path = String::from_utf8_lossy(&bytes);
+		// "/C:/folder" is "C:/folder".
+		let path = match path.as_bytes() {
+			[b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &path[1..],
+			_ => &path,
+		};
+		Some(PathBuf::from(path))
+	}
+}
+
+fn percent_decode(text: &str) -> Vec<u8> {
+	let bytes = text.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut index = 0;
+	while index < bytes.len() {
+		let byte = bytes[index];
+		let escaped = (byte == b'%')
+			.then(|| text.get(index + 1..index + 3))
+			.flatten()
+			.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+		match escaped {
+			Some(escaped) => {
+				decoded.push(escaped);
+				index += 3;
+			}
+			None => {
+				decoded.push(byte);
+				index += 1;
+			}
+		}
+	}
+
+	decoded
+}
+
+fn path_to_file_uri(path: &Path) -> Option<String> {
+	let path = std::path::absolute(path).ok()?;
+
+	#[cfg(unix)]
+	let bytes = {
+		use std::os::unix::ffi::OsStrExt;
+		path.as_os_str().as_bytes().to_vec()
+	};
+
+	#[cfg(not(unix))]
+	let bytes = {
+		// "C:\folder" is "/C:/folder".
+		let path = path.to_string_lossy().replace('\\', "/");
+		let path = path.strip_prefix("//?/").unwrap_or(&path);
+		// UNC paths already start with the host, "//server/share" is "file://server/share".
+		match path.starts_with('/') {
+			true => path.as_bytes().to_vec(),
+			false => format!("/{path}").into_bytes(),
+		}
+	};
+
+	Some(format!("file://{}", percent_encode(&bytes)))
+}
+
+fn percent_encode(bytes: &[u8]) -> String {
+	let mut encoded = String::with_capacity(bytes.len());
+	for byte in bytes {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+				encoded.push(*byte as char)
+			}
+			byte => encoded.push_str(&format!("%{byte:02X}")),
+		}
+	}
+
+	encoded
+}
+
+/// Encodes `CF_HDROP` with wide file names, see [`parse_hdrop`].
+fn encode_hdrop(paths: &[PathBuf]) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	bytes.extend_from_slice(&(DROPFILES_SIZE as u32).to_le_bytes());
+	// The drop point and whether it is in the non-client area.
+	bytes.extend_from_slice(&[0; 12]);
+	bytes.extend_from_slice(&1u32.to_le_bytes());
+
+	for path in paths {
+		let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+		for unit in path.to_string_lossy().encode_utf16().chain([0]) {
+			bytes.extend_from_slice(&unit.to_le_bytes());
+		}
+	}
+	bytes.extend_from_slice(&[0, 0]);
+
+	bytes
+}
+
+impl ClipboardFiles {
+	fn uris(&self) -> impl Iterator<Item = String> {
+		self.paths.iter().filter_map(|path| path_to_file_uri(path))
+	}
+}
+
+/// Parses `CF_HDROP`, which is a DROPFILES struct followed by a double NUL terminated list of
+/// NUL terminated file names.
+fn parse_hdrop(bytes: &[u8]) -> Option<Vec<PathBuf>> {
+	let files_offset = u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?) as usize;
+	let wide = u32::from_le_bytes(bytes.get(16..20)?.try_into().ok()?) != 0;
+	if files_offset < DROPFILES_SIZE {
+		return None;
+	}
+
+	let names = bytes.get(files_offset..)?;
+	let names = if wide {
+		utf16le(names)
+	} else {
+		// The ANSI code page is unknown here, Latin-1 is the closest guess.
+		names.iter().map(|byte| *byte as char).collect()
+	};
+
+	Some(
+		names
+			.split('\0')
+			.take_while(|name| !name.is_empty())
+			.map(PathBuf::from)
+			.collect(),
+	)
+}
+
+/// Parses `Preferred DropEffect`, which is a DROPEFFECT value.
+fn parse_drop_effect(bytes: &[u8]) -> Option<FileOperation> {
+	let effect = u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?);
+	Some(if effect & DROPEFFECT_MOVE != 0 {
+		FileOperation::Cut
+	} else {
+		FileOperation::Copy
+	})
+}
+
+impl ReadFromClipboard for ClipboardFiles {
+	fn is_available(mime_types: &[&str]) -> bool {
+		mime_types
+			.iter()
+			.any(|mime_type| [GNOME_COPIED_FILES, URI_LIST, CF_HDROP].contains(mime_type))
+	}
+
+	fn read(data: &DataAccess) -> Option<Self> {
+		if let Some(files) = data
+			.get_first_success(&[GNOME_COPIED_FILES])
+			.and_then(|bytes| parse_gnome_copied_files(&bytes))
+		{
+			return Some(files);
+		}
+
+		// Links copied in a browser are uri lists as well, but without any files.
+		if let Some(paths) = data
+			.get_first_success(&[URI_LIST])
+			.map(|bytes| parse_uri_list(&bytes))
+			.filter(|paths| !paths.is_empty())
+		{
+			let operation = match data.get_first_success(&[KDE_CUT_SELECTION]) {
+				Some(cut) if cut.starts_with(b"1") => FileOperation::Cut,
+				_ => FileOperation::Copy,
+			};
+
+			return Some(Self { paths, operation });
+		}
+
+		let paths = parse_hdrop(&data.get_first_success(&[CF_HDROP])?)?;
+		let operation = data
+			.get_first_success(&[PREFERRED_DROP_EFFECT])
+			.and_then(|bytes| parse_drop_effect(&bytes))
+			.unwrap_or(FileOperation::Copy);
+
+		Some(Self { paths, operation })
+	}
+}
+
+/// Raw types [`ClipboardFiles`] is written as.
+pub(super) fn write_types() -> Vec<String> {
+	WRITE_TYPES
+		.iter()
+		.map(|raw_type| raw_type.to_string())
+		.collect()
+}
+
+impl WriteToClipboard for ClipboardFiles {
+	fn viable_conversions(&self) -> Vec<String> {
+		write_types()
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		let cut = self.operation == FileOperation::Cut;
+		match mime_type {
+			GNOME_COPIED_FILES => {
+				let operation = if cut { "cut" } else { "copy" };
+				let lines: Vec<String> = std::iter::once(operation.to_string())
+					.chain(self.uris())
+					.collect();
+				Some(lines.join("\n").into_bytes())
+			}
+			// RFC 2483 asks for CRLF line ends.
+			URI_LIST => Some(
+				self.uris()
+					.map(|uri| uri + "\r\n")
+					.collect::<String>()
+					.into_bytes(),
+			),
+			KDE_CUT_SELECTION => Some(if cut { b"1".to_vec() } else { b"0".to_vec() }),
+			CF_HDROP => Some(encode_hdrop(&self.paths)),
+			PREFERRED_DROP_EFFECT => {
+				let effect = if cut {
+					DROPEFFECT_MOVE
+				} else {
+					DROPEFFECT_COPY
+				};
+				Some(effect.to_le_bytes().to_vec())
+			}
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_uri_list() {
+		let list = b"# copied by a file manager\r\nfile:///home/user/My%20Mod.zip\r\n\
+			https://example.com/not-a-file\r\nfile://localhost/tmp/save%2B1.dat\r\n";
+
+		assert_eq!(
+			parse_uri_list(list),
+			[
+				PathBuf::from("/home/user/My Mod.zip"),
+				PathBuf::from("/tmp/save+1.dat")
+			]
+		);
+	}
+
+	#[test]
+	fn ignores_uri_list_without_files() {
+		let snapshot = crate::ClipboardSnapshot::new(vec![
+			(
+				URI_LIST.to_string(),
+				b"https://example.com/page\r\n".to_vec(),
+			),
+			(
+				"text/plain;charset=utf-8".to_string(),
+				b"https://example.com/page".to_vec(),
+			),
+		]);
+
+		assert_eq!(snapshot.read_data::<ClipboardFiles>(), None);
+		assert_eq!(
+			snapshot
+				.as_data_access()
+				.read_first::<(ClipboardFiles, String)>(),
+			Some(crate::OneOf2::B("https://example.com/page".to_string()))
+		);
+	}
+
+	#[test]
+	fn rejects_remote_hosts() {
+		assert_eq!(file_uri_to_path("file://server/share/file"), None);
+	}
+
+	#[test]
+	fn parses_gnome_copied_files() {
+		let files = parse_gnome_copied_files(b"cut\nfile:///a/b.png\nfile:///c").unwrap();
+		assert_eq!(files.operation, FileOperation::Cut);
+		assert_eq!(
+			files.paths,
+			[PathBuf::from("/a/b.png"), PathBuf::from("/c")]
+		);
+
+		assert_eq!(parse_gnome_copied_files(b"link\nfile:///a"), None);
+	}
+
+	#[test]
+	fn parses_wide_hdrop() {
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&(DROPFILES_SIZE as u32).to_le_bytes());
+		bytes.extend_from_slice(&[0; 12]);
+		bytes.extend_from_slice(&1u32.to_le_bytes());
+		for unit in "C:\\mods\\a.zip\0C:\\b.dat\0\0".encode_utf16() {
+			bytes.extend_from_slice(&unit.to_le_bytes());
+		}
+
+		assert_eq!(
+			parse_hdrop(&bytes).unwrap(),
+			[PathBuf::from("C:\\mods\\a.zip"), PathBuf::from("C:\\b.dat")]
+		);
+	}
+
+	#[test]
+	fn parses_ansi_hdrop() {
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&(DROPFILES_SIZE as u32).to_le_bytes());
+		bytes.extend_from_slice(&[0; 16]);
+		bytes.extend_from_slice(b"C:\\x.txt\0\0");
+
+		assert_eq!(parse_hdrop(&bytes).unwrap(), [PathBuf::from("C:\\x.txt")]);
+		assert_eq!(parse_hdrop(&bytes[..10]), None);
+	}
+
+	#[test]
+	fn parses_drop_effect() {
+		assert_eq!(
+			parse_drop_effect(&2u32.to_le_bytes()),
+			Some(FileOperation::Cut)
+		);
+		assert_eq!(
+			parse_drop_effect(&5u32.to_le_bytes()),
+			Some(FileOperation::Copy)
+		);
+		assert_eq!(parse_drop_effect(&[1]), None);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn round_trips_written_files() {
+		let files = ClipboardFiles {
+			paths: vec![
+				PathBuf::from("/home/user/My Mod #1.zip"),
+				PathBuf::from("/tmp/ü.png"),
+			],
+			operation: FileOperation::Cut,
+		};
+
+		let uri_list = files.convert_to(URI_LIST).unwrap();
+		assert!(uri_list.starts_with(b"file:///home/user/My%20Mod%20%231.zip\r\n"));
+		assert_eq!(parse_uri_list(&uri_list), files.paths);
+
+		let gnome = files.convert_to(GNOME_COPIED_FILES).unwrap();
+		assert_eq!(parse_gnome_copied_files(&gnome), Some(files.clone()));
+
+		assert_eq!(files.convert_to(KDE_CUT_SELECTION).unwrap(), b"1");
+		assert_eq!(
+			parse_drop_effect(&files.convert_to(PREFERRED_DROP_EFFECT).unwrap()),
+			Some(FileOperation::Cut)
+		);
+	}
+
+	#[test]
+	fn encodes_hdrop() {
+		let paths = [PathBuf::from("/a/b.dat"), PathBuf::from("/c")];
+		assert_eq!(parse_hdrop(&encode_hdrop(&paths)).unwrap(), paths);
+	}
+}
@@ -0,0 +1,285 @@
+use super::text::{self, decode_with_bom};
+use crate::{DataAccess, ReadFromClipboard, WriteToClipboard, cf_html};
+
+/// Raw types, which contain HTML, in the order they are tried.
+const HTML_TYPES: [&str; 2] = ["text/html", "HTML Format"];
+
+/// Raw type HTML is written as, besides plain text.
+#[cfg(target_os = "windows")]
+const WRITE_TYPE: &str = "HTML Format";
+
+#[cfg(not(target_os = "windows"))]
+const WRITE_TYPE: &str = "text/html";
+
+/// Elements, whose content is not shown.
+const HIDDEN_ELEMENTS: [&str; 4] = ["script", "style", "head", "title"];
+
+/// Elements, which start on a new line.
+const BLOCK_ELEMENTS: [&str; 18] = [
+	"p",
+	"div",
+	"br",
+	"li",
+	"tr",
+	"h1",
+	"h2",
+	"h3",
+	"h4",
+	"h5",
+	"h6",
+	"ul",
+	"ol",
+	"table",
+	"blockquote",
+	"pre",
+	"hr",
+	"section",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardHtml {
+	/// The whole document, as offered by the source.
+	pub html: String,
+	/// The part of the document, which was actually copied.
+	pub fragment: String,
+	/// The page the content was copied from, if the source tells.
+	pub source_url: Option<String>,
+}
+
+impl ClipboardHtml {
+	/// Creates HTML to write, where the whole of `html` was copied.
+	pub fn new(html: String) -> Self {
+		Self {
+			fragment: html.clone(),
+			html,
+			source_url: None,
+		}
+	}
+
+	/// Renders the fragment as plain text, for targets which do not understand HTML.
+	pub fn to_plain_text(&self) -> String {
+		html_to_text(&self.fragment)
+	}
+
+	fn decode(raw_type: &str, bytes: &[u8]) -> Option<Self> {
+		match raw_type {
+			"HTML Format" => {
+				let parsed = cf_html::parse(bytes)?;
+				Some(Self {
+					html: parsed.html,
+					fragment: parsed.fragment,
+					source_url: parsed.source_url,
+				})
+			}
+			_ => {
+				// Firefox offers `text/html` as UTF-16 with a byte order mark on Linux.
+				let html = decode_with_bom(bytes);
+				let fragment = cf_html::fragment_between_comments(&html)
+					.unwrap_or(&html)
+					.to_string();
+
+				Some(Self {
+					html,
+					fragment,
+					source_url: None,
+				})
+			}
+		}
+	}
+}
+
+/// Turns HTML into text, keeping line breaks of block elements and dropping all tags.
+fn html_to_text(html: &str) -> String {
+	let mut text = String::new();
+	let mut hidden: Option<String> = None;
+	let mut rest = html;
+	// Whitespace is collapsed into a single space, as browsers do outside of `pre`.
+	let mut pending_space = false;
+
+	while !rest.is_empty() {
+		if let Some(after) = rest.strip_prefix("<!--") {
+			rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+			continue;
+		}
+
+		if rest.starts_with('<') {
+			// An unclosed tag ends the document.
+			let Some(end) = rest.find('>') else {
+				break;
+			};
+			let tag = &rest[1..end];
+			rest = &rest[end + 1..];
+
+			let closing = tag.starts_with('/');
+			let name = tag
+				.trim_start_matches('/')
+				.split(|char: char| char.is_whitespace() || char == '/')
+				.next()
+				.unwrap_or_default()
+				.to_ascii_lowercase();
+
+			match &hidden {
+				Some(element) if closing && *element == name => hidden = None,
+				Some(_) => {}
+				None if !closing && HIDDEN_ELEMENTS.contains(&name.as_str()) => hidden = Some(name),
+				None if BLOCK_ELEMENTS.contains(&name.as_str()) => {
+					if name == "br" || (!text.is_empty() && !text.ends_with('\n')) {
+						text.push('\n');
+					}
+					pending_space = false;
+				}
+				None if name == "td" || name == "th" => {
+					if !closing && !text.is_empty() && !text.ends_with(['\n', '\t']) {
+						text.push('\t');
+					}
+					pending_space = false;
+				}
+				None => {}
+			}
+			continue;
+		}
+
+		let end = rest.find('<').unwrap_or(rest.len());
+		if hidden.is_none() {
+			for char in decode_entities(&rest[..end]).chars() {
+				if char.is_whitespace() && char != '\u{a0}' {
+					pending_space = true;
+					continue;
+				}
+
+				if pending_space && !text.is_empty() && !text.ends_with(['\n', '\t']) {
+					text.push(' ');
+				}
+				pending_space = false;
+				text.push(char);
+			}
+		}
+		rest = &rest[end..];
+	}
+
+	text.trim_end().to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+	let mut decoded = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(start) = rest.find('&') {
+		decoded.push_str(&rest[..start]);
+		rest = &rest[start..];
+
+		let entity = rest[1..]
+			.find(';')
+			.filter(|end| *end <= 10)
+			.map(|end| &rest[1..end + 1]);
+		let char = entity.and_then(|entity| match entity {
+			"amp" => Some('&'),
+			"lt" => Some('<'),
+			"gt" => Some('>'),
+			"quot" => Some('"'),
+			"apos" => Some('\''),
+			"nbsp" => Some('\u{a0}'),
+			_ => {
+				let number = entity.strip_prefix('#')?;
+				let code = match number.strip_prefix(['x', 'X']) {
+					Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+					None => number.parse().ok()?,
+				};
+				char::from_u32(code)
+			}
+		});
+
+		match (entity, char) {
+			(Some(entity), Some(char)) => {
+				decoded.push(char);
+				rest = &rest[entity.len() + 2..];
+			}
+			_ => {
+				decoded.push('&');
+				rest = &rest[1..];
+			}
+		}
+	}
+
+	decoded.push_str(rest);
+	decoded
+}
+
+impl ReadFromClipboard for ClipboardHtml {
+	fn is_available(mime_types: &[&str]) -> bool {
+		mime_types
+			.iter()
+			.any(|mime_type| HTML_TYPES.contains(mime_type))
+	}
+
+	fn read(data: &DataAccess) -> Option<Self> {
+		let available = data.raw_types();
+		HTML_TYPES
+			.iter()
+			.filter(|raw_type| available.contains(raw_type))
+			.find_map(|raw_type| {
+				let bytes = data.get_raw_data(raw_type).ok()?;
+				Self::decode(raw_type, &bytes)
+			})
+	}
+}
+
+/// Raw types [`ClipboardHtml`] is written as.
+pub(super) fn write_types() -> Vec<String> {
+	let mut write_types = vec![WRITE_TYPE.to_string()];
+	write_types.extend(text::write_types());
+	write_types
+}
+
+/// Offers the HTML and a plain text rendering of the fragment.
+impl WriteToClipboard for ClipboardHtml {
+	fn viable_conversions(&self) -> Vec<String> {
+		write_types()
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		match mime_type {
+			"text/html" => Some(self.html.as_bytes().to_vec()),
+			"HTML Format" => Some(cf_html::encode(
+				&self.html,
+				&self.fragment,
+				self.source_url.as_deref(),
+			)),
+			_ => text::encode_text(mime_type, &self.to_plain_text()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_utf16_with_bom() {
+		let mut bytes = vec![0xff, 0xfe];
+		bytes.extend("<b>ä</b>".encode_utf16().flat_map(u16::to_le_bytes));
+		assert_eq!(decode_with_bom(&bytes), "<b>ä</b>");
+
+		let mut bytes = vec![0xfe, 0xff];
+		bytes.extend("<i>x</i>".encode_utf16().flat_map(u16::to_be_bytes));
+		assert_eq!(decode_with_bom(&bytes), "<i>x</i>");
+	}
+
+	#[test]
+	fn decodes_utf8() {
+		assert_eq!(decode_with_bom("<p>ü</p>\0".as_bytes()), "<p>ü</p>");
+		assert_eq!(decode_with_bom(b"\xef\xbb\xbf<p></p>"), "<p></p>");
+	}
+
+	#[test]
+	fn renders_plain_text() {
+		let html = "<html><head><style>p { color: red; }</style></head><body>\n\
+			<h1>Log</h1><p>Player  <b>one</b>&nbsp;won &amp; left.<br>Bye&#33;</p>\
+			<!-- comment --><table><tr><td>a</td><td>b</td></tr></table></body></html>";
+
+		assert_eq!(
+			html_to_text(html),
+			"Log\nPlayer one\u{a0}won & left.\nBye!\na\tb"
+		);
+		assert_eq!(html_to_text("a &unknown; b & c"), "a &unknown; b & c");
+	}
+}
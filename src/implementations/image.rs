@@ -0,0 +1,251 @@
+use crate::{DataAccess, ReadFromClipboard, WriteToClipboard, dib};
+
+/// Raw types, which can be decoded into a [`ClipboardImage`], in the order they are tried.
+///
+/// Lossless formats with alpha come first and JPEG comes last.
+#[cfg(feature = "image")]
+const IMAGE_TYPES: [&str; 7] = [
+	"image/png",
+	"PNG",
+	"CF_DIBV5",
+	"CF_DIB",
+	"image/bmp",
+	"image/jpeg",
+	"JFIF",
+];
+
+/// PNG and JPEG need the `image` feature.
+#[cfg(not(feature = "image"))]
+const IMAGE_TYPES: [&str; 3] = ["CF_DIBV5", "CF_DIB", "image/bmp"];
+
+/// Raw types an image is written as. Windows creates `CF_DIB` and `CF_BITMAP` from `CF_DIBV5`.
+#[cfg(all(target_os = "windows", feature = "image"))]
+const WRITE_TYPES: [&str; 2] = ["PNG", "CF_DIBV5"];
+
+#[cfg(all(target_os = "windows", not(feature = "image")))]
+const WRITE_TYPES: [&str; 1] = ["CF_DIBV5"];
+
+#[cfg(all(not(target_os = "windows"), feature = "image"))]
+const WRITE_TYPES: [&str; 2] = ["image/png", "image/bmp"];
+
+#[cfg(all(not(target_os = "windows"), not(feature = "image")))]
+const WRITE_TYPES: [&str; 1] = ["image/bmp"];
+
+/// Decoded image with 8 bit RGBA pixels, stored row by row from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardImage {
+	pub width: u32,
+	pub height: u32,
+	pub rgba: Vec<u8>,
+}
+
+impl ClipboardImage {
+	fn decode(raw_type: &str, bytes: &[u8]) -> Option<Self> {
+		match raw_type {
+			"CF_DIBV5" | "CF_DIB" => dib::decode(bytes),
+			"image/bmp" => dib::decode_bmp(bytes),
+			#[cfg(feature = "image")]
+			"image/png" | "PNG" => Self::decode_with_codec(bytes, ::image::ImageFormat::Png),
+			#[cfg(feature = "image")]
+			"image/jpeg" | "JFIF" => Self::decode_with_codec(bytes, ::image::ImageFormat::Jpeg),
+			_ => None,
+		}
+	}
+
+	#[cfg(feature = "image")]
+	fn decode_with_codec(bytes: &[u8], format: ::image::ImageFormat) -> Option<Self> {
+		match ::image::load_from_memory_with_format(bytes, format) {
+			Ok(image) => {
+				let image = image.into_rgba8();
+				Some(Self {
+					width: image.width(),
+					height: image.height(),
+					rgba: image.into_raw(),
+				})
+			}
+			Err(error) => {
+				log::warn!("Failed to decode {format:?}: {error}");
+				None
+			}
+		}
+	}
+
+	#[cfg(feature = "image")]
+	fn encode_png(&self) -> Option<Vec<u8>> {
+		use ::image::ImageEncoder;
+
+		let size = (self.width as usize)
+			.checked_mul(self.height as usize)?
+			.checked_mul(4)?;
+		if self.rgba.len() != size {
+			return None;
+		}
+
+		let mut png = Vec::new();
+		let result = ::image::codecs::png::PngEncoder::new(&mut png).write_image(
+			&self.rgba,
+			self.width,
+			self.height,
+			::image::ExtendedColorType::Rgba8,
+		);
+
+		match result {
+			Ok(()) => Some(png),
+			Err(error) => {
+				log::error!("Failed to encode PNG: {error}");
+				None
+			}
+		}
+	}
+}
+
+impl ReadFromClipboard for ClipboardImage {
+	fn is_available(mime_types: &[&str]) -> bool {
+		mime_types
+			.iter()
+			.any(|mime_type| IMAGE_TYPES.contains(mime_type))
+	}
+
+	fn read(data: &DataAccess) -> Option<Self> {
+		let available = data.raw_types();
+		IMAGE_TYPES
+			.iter()
+			.filter(|raw_type| available.contains(raw_type))
+			.find_map(|raw_type| {
+				let bytes = data.get_raw_data(raw_type).ok()?;
+				Self::decode(raw_type, &bytes)
+			})
+	}
+}
+
+/// Raw types [`ClipboardImage`] is written as.
+pub(super) fn write_types() -> Vec<String> {
+	WRITE_TYPES
+		.iter()
+		.map(|raw_type| raw_type.to_string())
+		.collect()
+}
+
+/// Only the formats a paste target asks for are encoded.
+impl WriteToClipboard for ClipboardImage {
+	fn viable_conversions(&self) -> Vec<String> {
+		write_types()
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		match mime_type {
+			"CF_DIBV5" => dib::encode_dibv5(self),
+			"image/bmp" => dib::encode_bmp(self),
+			#[cfg(feature = "image")]
+			"image/png" | "PNG" => self.encode_png(),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ClipboardSnapshot;
+
+	fn image(red: u8) -> ClipboardImage {
+		ClipboardImage {
+			width: 2,
+			height: 1,
+			rgba: vec![red, 0, 0, 255, 0, 0, 255, 255],
+		}
+	}
+
+	fn read(entries: Vec<(&str, Vec<u8>)>) -> Option<ClipboardImage> {
+		let entries = entries
+			.into_iter()
+			.map(|(raw_type, bytes)| (raw_type.to_string(), bytes))
+			.collect();
+		ClipboardSnapshot::new(entries).read_data::<ClipboardImage>()
+	}
+
+	#[test]
+	fn reads_dibv5_before_bmp() {
+		let bmp = dib::encode_bmp(&image(1)).unwrap();
+		let dibv5 = dib::encode_dibv5(&image(2)).unwrap();
+		assert_eq!(
+			read(vec![("image/bmp", bmp.clone()), ("CF_DIBV5", dibv5)]),
+			Some(image(2))
+		);
+		assert_eq!(read(vec![("image/bmp", bmp)]), Some(image(1)));
+	}
+
+	#[test]
+	fn falls_through_corrupt_formats() {
+		let bmp = dib::encode_bmp(&image(1)).unwrap();
+		assert_eq!(
+			read(vec![("CF_DIBV5", vec![1, 2, 3]), ("image/bmp", bmp)]),
+			Some(image(1))
+		);
+		assert_eq!(read(vec![("CF_DIBV5", vec![1, 2, 3])]), None);
+	}
+
+	#[cfg(feature = "image")]
+	#[test]
+	fn reads_png_before_dibv5() {
+		let png = image(1).convert_to("image/png").unwrap();
+		let dibv5 = dib::encode_dibv5(&image(2)).unwrap();
+		assert_eq!(
+			read(vec![("CF_DIBV5", dibv5.clone()), ("PNG", png)]),
+			Some(image(1))
+		);
+		assert_eq!(
+			read(vec![
+				("image/png", b"\x89PNG garbage".to_vec()),
+				("CF_DIBV5", dibv5)
+			]),
+			Some(image(2))
+		);
+	}
+
+	#[cfg(not(feature = "image"))]
+	#[test]
+	fn only_reads_dib_and_bmp_without_codecs() {
+		assert!(!ClipboardImage::is_available(&[
+			"image/png",
+			"PNG",
+			"image/jpeg",
+			"JFIF"
+		]));
+		assert!(ClipboardImage::is_available(&["image/png", "CF_DIB"]));
+
+		let bmp = dib::encode_bmp(&image(1)).unwrap();
+		assert_eq!(
+			read(vec![("image/png", b"\x89PNG".to_vec()), ("image/bmp", bmp)]),
+			Some(image(1))
+		);
+		assert_eq!(read(vec![("image/png", b"\x89PNG".to_vec())]), None);
+	}
+
+	#[cfg(feature = "image")]
+	#[test]
+	fn round_trips_png() {
+		let image = ClipboardImage {
+			width: 2,
+			height: 1,
+			rgba: vec![255, 0, 0, 255, 0, 0, 255, 128],
+		};
+
+		let png = image.convert_to("image/png").unwrap();
+		assert!(png.starts_with(b"\x89PNG"));
+		assert_eq!(ClipboardImage::decode("PNG", &png), Some(image));
+	}
+
+	#[cfg(feature = "image")]
+	#[test]
+	fn rejects_wrong_pixel_counts() {
+		let image = ClipboardImage {
+			width: 2,
+			height: 2,
+			rgba: vec![0; 12],
+		};
+
+		assert_eq!(image.convert_to("image/png"), None);
+		assert_eq!(image.convert_to("image/bmp"), None);
+	}
+}
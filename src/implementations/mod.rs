@@ -0,0 +1,19 @@
+mod builder;
+mod code_page;
+#[cfg(feature = "serde")]
+mod custom_format;
+mod files;
+mod html;
+mod image;
+mod rtf;
+mod text;
+mod url;
+
+pub use self::image::ClipboardImage;
+pub use builder::ClipboardContentBuilder;
+#[cfg(feature = "serde")]
+pub use custom_format::{Codec, CustomData, CustomFormat, Migration};
+pub use files::{ClipboardFiles, FileOperation};
+pub use html::ClipboardHtml;
+pub use rtf::Rtf;
+pub use url::ClipboardUrl;
@@ -0,0 +1,286 @@
+use super::code_page;
+use crate::{DataAccess, ReadFromClipboard};
+
+const RTF_TYPES: [&str; 3] = ["text/rtf", "application/rtf", "Rich Text Format"];
+
+/// Destinations, whose content is not part of the document text.
+const SKIPPED_DESTINATIONS: [&str; 18] = [
+	"fonttbl",
+	"colortbl",
+	"stylesheet",
+	"info",
+	"pict",
+	"header",
+	"headerl",
+	"headerr",
+	"footer",
+	"footerl",
+	"footerr",
+	"listtable",
+	"listoverridetable",
+	"rsidtbl",
+	"generator",
+	"xmlnstbl",
+	"fldinst",
+	"object",
+];
+
+/// Rich text, together with a best-effort plain text version of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rtf {
+	/// The document, which is ASCII apart from bytes in its code page and `\bin` data.
+	pub raw: Vec<u8>,
+	/// Paragraphs are separated by newlines. Formatting, tables and embedded objects are lost.
+	pub text: String,
+}
+
+impl Rtf {
+	pub fn new(raw: Vec<u8>) -> Self {
+		let text = extract_text(&raw);
+		Self { raw, text }
+	}
+}
+
+#[derive(Clone, Copy)]
+struct GroupState {
+	skip: bool,
+	/// Number of fallback characters following a `\uN`.
+	unicode_skip: usize,
+}
+
+struct TextExtractor<'a> {
+	rtf: &'a [u8],
+	index: usize,
+	text: String,
+	state: GroupState,
+	stack: Vec<GroupState>,
+	code_page: i32,
+	/// Fallback characters of the last `\uN`, which are still to be skipped.
+	pending_skip: usize,
+	high_surrogate: Option<u16>,
+}
+
+/// Extracts the text of an RTF document.
+///
+/// The single byte Windows code pages are known, bytes of other code pages are decoded as Latin-1.
+fn extract_text(rtf: &[u8]) -> String {
+	let mut extractor = TextExtractor {
+		rtf,
+		index: 0,
+		text: String::new(),
+		state: GroupState {
+			skip: false,
+			unicode_skip: 1,
+		},
+		stack: Vec::new(),
+		code_page: 1252,
+		pending_skip: 0,
+		high_surrogate: None,
+	};
+
+	extractor.run();
+	extractor.text
+}
+
+impl TextExtractor<'_> {
+	fn run(&mut self) {
+		while let Some(&byte) = self.rtf.get(self.index) {
+			self.index += 1;
+			match byte {
+				b'{' => {
+					self.stack.push(self.state);
+					self.pending_skip = 0;
+				}
+				b'}' => {
+					self.state = self.stack.pop().unwrap_or(self.state);
+					self.pending_skip = 0;
+				}
+				b'\\' => self.control(),
+				b'\r' | b'\n' | 0 => {}
+				byte => self.push_byte(byte),
+			}
+		}
+	}
+
+	fn push(&mut self, char: char) {
+		if !self.state.skip {
+			self.text.push(char);
+		}
+	}
+
+	/// Pushes a character from the document's code page, unless it is a `\uN` fallback.
+	fn push_byte(&mut self, byte: u8) {
+		if self.pending_skip > 0 {
+			self.pending_skip -= 1;
+			return;
+		}
+
+		let char = code_page::decode_byte(self.code_page, byte).unwrap_or(byte as char);
+		self.push(char);
+	}
+
+	fn push_unicode(&mut self, value: i32) {
+		// Values above 32767 are written as negative numbers.
+		let unit = if value < 0 { value + 65536 } else { value } as u16;
+		self.pending_skip = self.state.unicode_skip;
+
+		if self.state.skip {
+			return;
+		}
+
+		match (self.high_surrogate.take(), unit) {
+			(_, 0xd800..=0xdbff) => self.high_surrogate = Some(unit),
+			(Some(high), 0xdc00..=0xdfff) => {
+				if let Some(char) = char::decode_utf16([high, unit]).next().and_then(Result::ok) {
+					self.push(char);
+				}
+			}
+			(_, unit) => self.push(char::from_u32(unit as u32).unwrap_or('\u{fffd}')),
+		}
+	}
+
+	fn control(&mut self) {
+		let Some(&next) = self.rtf.get(self.index) else {
+			return;
+		};
+
+		if !next.is_ascii_alphabetic() {
+			self.index += 1;
+			match next {
+				b'\'' => {
+					let byte = self
+						.rtf
+						.get(self.index..self.index + 2)
+						.and_then(|hex| std::str::from_utf8(hex).ok())
+						.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+					// Malformed escapes are dropped as well.
+					self.index = (self.index + 2).min(self.rtf.len());
+					if let Some(byte) = byte {
+						self.push_byte(byte);
+					}
+				}
+				b'*' => self.state.skip = true,
+				b'~' => self.push('\u{a0}'),
+				b'_' => self.push('\u{2011}'),
+				b'\r' | b'\n' => self.push('\n'),
+				b'\\' | b'{' | b'}' => self.push_byte(next),
+				// Optional hyphens and unknown symbols.
+				_ => {}
+			}
+			return;
+		}
+
+		let start = self.index;
+		while self
+			.rtf
+			.get(self.index)
+			.is_some_and(u8::is_ascii_alphabetic)
+		{
+			self.index += 1;
+		}
+		let word = String::from_utf8_lossy(&self.rtf[start..self.index]).into_owned();
+
+		let parameter_start = self.index;
+		if self.rtf.get(self.index) == Some(&b'-') {
+			self.index += 1;
+		}
+		while self.rtf.get(self.index).is_some_and(u8::is_ascii_digit) {
+			self.index += 1;
+		}
+		let parameter = std::str::from_utf8(&self.rtf[parameter_start..self.index])
+			.ok()
+			.and_then(|parameter| parameter.parse::<i32>().ok());
+
+		// A space only delimits the control word.
+		if self.rtf.get(self.index) == Some(&b' ') {
+			self.index += 1;
+		}
+
+		match word.as_str() {
+			"par" | "line" | "sect" | "page" | "row" => self.push('\n'),
+			"tab" | "cell" => self.push('\t'),
+			"u" => {
+				if let Some(value) = parameter {
+					self.push_unicode(value);
+				}
+			}
+			"uc" => self.state.unicode_skip = parameter.unwrap_or(1).max(0) as usize,
+			"ansicpg" => self.code_page = parameter.unwrap_or(1252),
+			"bin" => self.index += parameter.unwrap_or(0).max(0) as usize,
+			"lquote" => self.push('‘'),
+			"rquote" => self.push('’'),
+			"ldblquote" => self.push('“'),
+			"rdblquote" => self.push('”'),
+			"bullet" => self.push('•'),
+			"endash" => self.push('–'),
+			"emdash" => self.push('—'),
+			"enspace" | "emspace" | "qmspace" => self.push(' '),
+			word if SKIPPED_DESTINATIONS.contains(&word) => self.state.skip = true,
+			_ => {}
+		}
+	}
+}
+
+impl ReadFromClipboard for Rtf {
+	fn is_available(mime_types: &[&str]) -> bool {
+		mime_types
+			.iter()
+			.any(|mime_type| RTF_TYPES.contains(mime_type))
+	}
+
+	fn read(data: &DataAccess) -> Option<Self> {
+		let mut raw = data.get_first_success(&RTF_TYPES)?;
+		while raw.last() == Some(&0) {
+			raw.pop();
+		}
+
+		Some(Self::new(raw))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn skips_tables_and_keeps_paragraphs() {
+		let rtf = r"{\rtf1\ansi\ansicpg1252\deff0{\fonttbl{\f0\fswiss Arial;}}{\colortbl;\red255\green0\blue0;}
+{\*\generator Writer}\pard\f0 Hello {\b world}!\par Second line\par}";
+
+		assert_eq!(extract_text(rtf.as_bytes()), "Hello world!\nSecond line\n");
+	}
+
+	#[test]
+	fn decodes_unicode_escapes() {
+		let rtf = r"{\rtf1\uc1 Gr\u252?\'fc\u-10179?\u-8704? \uc2\u8364\'80\'80 done}";
+		assert_eq!(extract_text(rtf.as_bytes()), "Grüü😀 € done");
+	}
+
+	#[test]
+	fn decodes_code_page_and_symbols() {
+		let rtf = r"{\rtf1\ansi\ansicpg1252 \'93quoted\'94\~\{braces\}\\\tab x\emdash y}";
+		assert_eq!(
+			extract_text(rtf.as_bytes()),
+			"“quoted”\u{a0}{braces}\\\tx—y"
+		);
+	}
+
+	#[test]
+	fn reads_other_code_pages() {
+		let mut rtf = br"{\rtf1\ansi\ansicpg1251 \'cf\'f0\'e8\'e2\'e5\'f2, ".to_vec();
+		// Literal bytes of the code page, which are not valid UTF-8.
+		rtf.extend_from_slice(b"\xec\xe8\xf0}\0");
+
+		let snapshot = crate::ClipboardSnapshot::new(vec![("text/rtf".to_string(), rtf)]);
+		let read = snapshot.read_data::<Rtf>().unwrap();
+		assert_eq!(read.text, "Привет, мир");
+		assert!(read.raw.ends_with(b"\xec\xe8\xf0}"));
+	}
+
+	#[test]
+	fn survives_truncated_input() {
+		let rtf = r"{\rtf1 text\'9";
+		assert_eq!(extract_text(rtf.as_bytes()), "text");
+		assert_eq!(extract_text(br"\u"), "");
+	}
+}
@@ -0,0 +1,433 @@
+//! Decoders for the many ways platforms encode plain text.
+
+use crate::{DataAccess, ReadFromClipboard, WriteToClipboard};
+
+/// Raw types with text, in the order they are tried.
+///
+/// Unicode formats come first. `text/plain` with other charsets is tried after UTF-16.
+const TEXT_TYPES: [&str; 10] = [
+	"text/plain;charset=utf-8",
+	"UTF8_STRING",
+	"CF_UNICODETEXT",
+	"text/plain;charset=utf-16le",
+	"text/plain",
+	"COMPOUND_TEXT",
+	"STRING",
+	"TEXT",
+	"CF_TEXT",
+	"CF_OEMTEXT",
+];
+
+/// Raw types text is written as. Windows only gets its own format, as other names would be
+/// registered as new formats.
+#[cfg(target_os = "windows")]
+const WRITE_TYPES: [&str; 1] = ["CF_UNICODETEXT"];
+
+/// Raw types text is written as, in the order of [`TEXT_TYPES`].
+#[cfg(not(target_os = "windows"))]
+const WRITE_TYPES: [&str; 5] = [
+	"text/plain;charset=utf-8",
+	"UTF8_STRING",
+	"text/plain",
+	"STRING",
+	"TEXT",
+];
+
+/// Characters 0x80 to 0x9F of Windows-1252, which is where it differs from Latin-1.
+const CP1252_HIGH: [char; 32] = [
+	'€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+	'\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// Characters 0x80 to 0xFF of code page 437, the OEM code page of US Windows.
+const CP437_HIGH: [char; 128] = [
+	'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ',
+	'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ',
+	'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕',
+	'╣', '║', '╗', '╝', '╜', '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦',
+	'╠', '═', '╬', '╧', '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐',
+	'▀', 'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±',
+	'≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+const ESC: u8 = 0x1b;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+	Utf8,
+	/// UTF-16 with a byte order mark, little endian without one.
+	Utf16,
+	Utf16Le,
+	Utf16Be,
+	Latin1,
+	Windows1252,
+	Cp437,
+}
+
+impl Charset {
+	fn from_name(name: &str) -> Option<Self> {
+		let name = name.trim().trim_matches('"').to_ascii_lowercase();
+		Some(match name.as_str() {
+			"utf-8" | "utf8" | "us-ascii" | "ascii" => Charset::Utf8,
+			"utf-16" | "utf16" | "ucs-2" => Charset::Utf16,
+			"utf-16le" => Charset::Utf16Le,
+			"utf-16be" => Charset::Utf16Be,
+			"iso-8859-1" | "iso_8859-1" | "latin1" | "l1" => Charset::Latin1,
+			"windows-1252" | "cp1252" => Charset::Windows1252,
+			"ibm437" | "cp437" => Charset::Cp437,
+			_ => return None,
+		})
+	}
+
+	fn decode(self, bytes: &[u8]) -> String {
+		match self {
+			Charset::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+			Charset::Utf16 => match bytes {
+				[0xfe, 0xff, ..] => utf16(bytes, u16::from_be_bytes),
+				_ => utf16(bytes, u16::from_le_bytes),
+			},
+			Charset::Utf16Le => utf16(bytes, u16::from_le_bytes),
+			Charset::Utf16Be => utf16(bytes, u16::from_be_bytes),
+			Charset::Latin1 => bytes.iter().map(|byte| *byte as char).collect(),
+			Charset::Windows1252 => bytes.iter().map(|byte| windows_1252(*byte)).collect(),
+			Charset::Cp437 => bytes
+				.iter()
+				.map(|byte| match byte {
+					0x80..=0xff => CP437_HIGH[*byte as usize - 0x80],
+					byte => *byte as char,
+				})
+				.collect(),
+		}
+	}
+}
+
+pub(super) fn windows_1252(byte: u8) -> char {
+	match byte {
+		0x80..=0x9f => CP1252_HIGH[byte as usize - 0x80],
+		byte => byte as char,
+	}
+}
+
+/// Decodes UTF-16, ignoring a trailing odd byte.
+fn utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+	let units: Vec<u16> = bytes
+		.chunks_exact(2)
+		.map(|unit| from_bytes([unit[0], unit[1]]))
+		.collect();
+	String::from_utf16_lossy(&units)
+}
+
+pub(super) fn utf16le(bytes: &[u8]) -> String {
+	utf16(bytes, u16::from_le_bytes)
+}
+
+/// Decodes text, whose encoding is only given by a byte order mark. Without one it is UTF-8.
+pub(super) fn decode_with_bom(bytes: &[u8]) -> String {
+	let text = match bytes {
+		[0xff, 0xfe, ..] | [0xfe, 0xff, ..] => Charset::Utf16.decode(bytes),
+		_ => Charset::Utf8.decode(bytes),
+	};
+
+	strip(text, false)
+}
+
+/// Removes the byte order mark, and everything from the first NUL on if the format is NUL
+/// terminated. Otherwise only trailing NULs are removed.
+fn strip(text: String, nul_terminated: bool) -> String {
+	let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+	let text = match nul_terminated {
+		true => text.split('\0').next().unwrap_or_default(),
+		false => text.trim_end_matches('\0'),
+	};
+
+	text.to_string()
+}
+
+/// Splits a mime type into its lowercase essence and its charset parameter.
+fn essence_and_charset(mime_type: &str) -> (String, Option<&str>) {
+	let mut parts = mime_type.split(';');
+	let essence = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+	let charset = parts.find_map(|parameter| {
+		let (name, value) = parameter.split_once('=')?;
+		name.trim().eq_ignore_ascii_case("charset").then_some(value)
+	});
+
+	(essence, charset)
+}
+
+/// Decodes the text in `bytes` as the format `raw_type` demands.
+///
+/// Returns `None` for formats, which are not text, and for unknown charsets.
+pub(crate) fn decode_text(raw_type: &str, bytes: &[u8]) -> Option<String> {
+	let (charset, nul_terminated) = match raw_type {
+		"UTF8_STRING" => (Charset::Utf8, false),
+		// ICCCM defines STRING as Latin-1. TEXT is whatever the owner chose, mostly one of them.
+		"STRING" => (Charset::Latin1, false),
+		"TEXT" if std::str::from_utf8(bytes).is_ok() => (Charset::Utf8, false),
+		"TEXT" if bytes.contains(&ESC) => return Some(strip(decode_compound_text(bytes), false)),
+		"TEXT" => (Charset::Latin1, false),
+		"COMPOUND_TEXT" => return Some(strip(decode_compound_text(bytes), false)),
+		"CF_UNICODETEXT" => (Charset::Utf16Le, true),
+		// The ANSI and OEM code pages depend on the system, these are the ones of US Windows.
+		"CF_TEXT" => (Charset::Windows1252, true),
+		"CF_OEMTEXT" => (Charset::Cp437, true),
+		mime_type => {
+			let (essence, charset) = essence_and_charset(mime_type);
+			if essence != "text/plain" {
+				return None;
+			}
+
+			// Without a charset, UTF-8 is the only reasonable guess nowadays.
+			let charset = match charset {
+				Some(charset) => Charset::from_name(charset)?,
+				None => Charset::Utf8,
+			};
+			(charset, false)
+		}
+	};
+
+	Some(strip(charset.decode(bytes), nul_terminated))
+}
+
+/// Decodes X11 compound text, which is ISO 2022 with Latin-1 as the default.
+///
+/// Only ASCII, Latin-1 and UTF-8 segments are supported. Other character sets are replaced by
+/// U+FFFD, as they would need large conversion tables.
+fn decode_compound_text(bytes: &[u8]) -> String {
+	#[derive(Clone, Copy, PartialEq, Eq)]
+	enum Segment {
+		Default,
+		Utf8,
+		Unknown,
+	}
+
+	let mut text = String::new();
+	let mut utf8 = Vec::new();
+	let mut segment = Segment::Default;
+	let mut index = 0;
+
+	while index < bytes.len() {
+		let byte = bytes[index];
+		if byte != ESC {
+			match segment {
+				Segment::Utf8 => utf8.push(byte),
+				Segment::Default => text.push(byte as char),
+				Segment::Unknown if byte.is_ascii_control() => text.push(byte as char),
+				Segment::Unknown => text.push('\u{fffd}'),
+			}
+			index += 1;
+			continue;
+		}
+
+		text.push_str(&String::from_utf8_lossy(&utf8));
+		utf8.clear();
+
+		// An escape sequence is ESC, intermediate bytes 0x20..=0x2F and a final byte.
+		let mut end = index + 1;
+		while bytes
+			.get(end)
+			.is_some_and(|byte| (0x20..=0x2f).contains(byte))
+		{
+			end += 1;
+		}
+		let sequence = bytes.get(index + 1..=end).unwrap_or(&bytes[index + 1..]);
+		index = end + 1;
+
+		segment = match sequence {
+			// ASCII into GL, Latin-1 right half into GR.
+			b"(B" | b"-A" => Segment::Default,
+			b"%G" => Segment::Utf8,
+			b"%@" => Segment::Default,
+			// Extended segments with a length, whose encoding is named in the segment.
+			[b'%', b'/', b'0'..=b'4'] => {
+				let length = bytes
+					.get(index..index + 2)
+					.map(|length| ((length[0] as usize & 0x7f) << 7) | (length[1] as usize & 0x7f))
+					.unwrap_or(0);
+				index += 2 + length;
+				text.push('\u{fffd}');
+				segment
+			}
+			_ => Segment::Unknown,
+		};
+	}
+
+	text.push_str(&String::from_utf8_lossy(&utf8));
+	text
+}
+
+impl ReadFromClipboard for String {
+	fn is_available(mime_types: &[&str]) -> bool {
+		mime_types
+			.iter()
+			.any(|mime_type| decode_text(mime_type, &[]).is_some())
+	}
+
+	fn read(data: &DataAccess) -> Option<Self> {
+		let available = data.raw_types();
+
+		// Exact names first, then any text/plain with a charset we know.
+		let others = available.iter().filter(|raw_type| {
+			!TEXT_TYPES.contains(raw_type) && decode_text(raw_type, &[]).is_some()
+		});
+
+		TEXT_TYPES
+			.iter()
+			.filter(|raw_type| available.contains(raw_type))
+			.chain(others)
+			.find_map(|raw_type| {
+				let bytes = data.get_raw_data(raw_type).ok()?;
+				decode_text(raw_type, &bytes)
+			})
+	}
+}
+
+/// Encodes `text` as the format `raw_type` demands, the reverse of [`decode_text`].
+///
+/// Characters, which Latin-1 lacks, are written as `?` into `STRING`.
+pub(crate) fn encode_text(raw_type: &str, text: &str) -> Option<Vec<u8>> {
+	match raw_type {
+		"text/plain;charset=utf-8" | "UTF8_STRING" | "text/plain" | "TEXT" => {
+			Some(text.as_bytes().to_vec())
+		}
+		"STRING" => Some(
+			text.chars()
+				.map(|char| u8::try_from(char).unwrap_or(b'?'))
+				.collect(),
+		),
+		"CF_UNICODETEXT" => Some(
+			text.encode_utf16()
+				.chain([0])
+				.flat_map(u16::to_le_bytes)
+				.collect(),
+		),
+		_ => None,
+	}
+}
+
+/// Raw types [`String`] is written as.
+pub(super) fn write_types() -> Vec<String> {
+	WRITE_TYPES
+		.iter()
+		.map(|raw_type| raw_type.to_string())
+		.collect()
+}
+
+impl WriteToClipboard for String {
+	fn viable_conversions(&self) -> Vec<String> {
+		write_types()
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		encode_text(mime_type, self)
+	}
+}
+
+impl WriteToClipboard for &'static str {
+	fn viable_conversions(&self) -> Vec<String> {
+		write_types()
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		encode_text(mime_type, self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn utf16le_bytes(text: &str) -> Vec<u8> {
+		text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+	}
+
+	#[test]
+	fn decodes_unicode_text_without_nul() {
+		let bytes = utf16le_bytes("Grüße\0garbage");
+		assert_eq!(decode_text("CF_UNICODETEXT", &bytes).unwrap(), "Grüße");
+	}
+
+	#[test]
+	fn ignores_odd_trailing_byte() {
+		let mut bytes = utf16le_bytes("ab");
+		bytes.push(0x63);
+		assert_eq!(decode_text("CF_UNICODETEXT", &bytes).unwrap(), "ab");
+		assert_eq!(decode_text("CF_UNICODETEXT", &[0x61]).unwrap(), "");
+	}
+
+	#[test]
+	fn decodes_charsets() {
+		let mut bytes = vec![0xff, 0xfe];
+		bytes.extend(utf16le_bytes("hi"));
+		assert_eq!(
+			decode_text("text/plain;charset=utf-16", &bytes).unwrap(),
+			"hi"
+		);
+		assert_eq!(
+			decode_text("text/plain; charset=\"UTF-16LE\"", &utf16le_bytes("x")).unwrap(),
+			"x"
+		);
+		assert_eq!(
+			decode_text("text/plain;charset=iso-8859-1", b"caf\xe9").unwrap(),
+			"café"
+		);
+		assert_eq!(decode_text("text/plain;charset=koi8-r", b"x"), None);
+		assert_eq!(
+			decode_text("text/plain;charset=utf-8", "\u{feff}bom\0".as_bytes()).unwrap(),
+			"bom"
+		);
+	}
+
+	#[test]
+	fn decodes_x11_formats() {
+		assert_eq!(decode_text("STRING", b"na\xefve").unwrap(), "naïve");
+		assert_eq!(decode_text("TEXT", "naïve".as_bytes()).unwrap(), "naïve");
+		assert_eq!(decode_text("TEXT", b"na\xefve").unwrap(), "naïve");
+		assert_eq!(
+			decode_text(
+				"COMPOUND_TEXT",
+				b"a\xe9\x1b%G\xe2\x82\xac\x1b%@b\x1b$)A\xb0\xa1"
+			)
+			.unwrap(),
+			"aé€b\u{fffd}\u{fffd}"
+		);
+	}
+
+	#[test]
+	fn decodes_windows_code_pages() {
+		assert_eq!(decode_text("CF_TEXT", b"\x93hi\x94\0x").unwrap(), "“hi”");
+		assert_eq!(
+			decode_text("CF_OEMTEXT", b"\x81ber\xb0\0").unwrap(),
+			"über░"
+		);
+	}
+
+	#[test]
+	fn round_trips_written_text() {
+		let text = "Grüße, 世界";
+		for raw_type in WRITE_TYPES.iter().chain(&["CF_UNICODETEXT", "STRING"]) {
+			let bytes = encode_text(raw_type, text).unwrap();
+			let expected = match *raw_type {
+				"STRING" => "Grüße, ??",
+				_ => text,
+			};
+			assert_eq!(
+				decode_text(raw_type, &bytes).unwrap(),
+				expected,
+				"{raw_type}"
+			);
+		}
+
+		assert!(
+			encode_text("CF_UNICODETEXT", "a")
+				.unwrap()
+				.ends_with(&[0, 0])
+		);
+	}
+
+	#[test]
+	fn rejects_other_formats() {
+		assert_eq!(decode_text("image/png", b"x"), None);
+		assert_eq!(decode_text("text/html", b"x"), None);
+	}
+}
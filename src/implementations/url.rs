@@ -0,0 +1,145 @@
+use super::text::{decode_text, utf16le};
+use crate::{DataAccess, ReadFromClipboard};
+
+const MOZ_URL: &str = "text/x-moz-url";
+const NETSCAPE_URL: &str = "_NETSCAPE_URL";
+const X_URI: &str = "text/x-uri";
+const URL_W: &str = "UniformResourceLocatorW";
+/// Same as [`URL_W`] in the ANSI code page, which older Windows programs only offer.
+const URL_A: &str = "UniformResourceLocator";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardUrl {
+	pub url: String,
+	/// The title of the linked page, if the source offers one.
+	pub title: Option<String>,
+}
+
+impl ClipboardUrl {
+	/// Parses "url\ntitle", where the title is optional.
+	fn from_lines(text: &str) -> Option<Self> {
+		let mut lines = text.trim_end_matches('\0').lines();
+		let url = lines.next()?.trim();
+		let title = lines
+			.next()
+			.map(str::trim)
+			.filter(|title| !title.is_empty());
+
+		is_url(url).then(|| Self {
+			url: url.to_string(),
+			title: title.map(str::to_string),
+		})
+	}
+}
+
+/// Checks for an absolute URL, like "https://host/path" or "mailto:name@host".
+fn is_url(text: &str) -> bool {
+	let Some((scheme, rest)) = text.split_once(':') else {
+		return false;
+	};
+
+	let valid_scheme = scheme
+		.chars()
+		.next()
+		.is_some_and(|first| first.is_ascii_alphabetic())
+		&& scheme
+			.chars()
+			.all(|char| char.is_ascii_alphanumeric() || matches!(char, '+' | '-' | '.'));
+	if !valid_scheme || text.chars().any(char::is_whitespace) {
+		return false;
+	}
+
+	match rest.strip_prefix("//") {
+		// Only file URLs may leave out the host.
+		Some(authority) if scheme.eq_ignore_ascii_case("file") => !authority.is_empty(),
+		Some(authority) => !authority.is_empty() && !authority.starts_with('/'),
+		None => scheme.eq_ignore_ascii_case("mailto") && !rest.is_empty(),
+	}
+}
+
+impl ReadFromClipboard for ClipboardUrl {
+	fn is_available(mime_types: &[&str]) -> bool {
+		mime_types
+			.iter()
+			.any(|mime_type| [MOZ_URL, NETSCAPE_URL, X_URI, URL_W, URL_A].contains(mime_type))
+			|| String::is_available(mime_types)
+	}
+
+	fn read(data: &DataAccess) -> Option<Self> {
+		let parsed = data
+			.get_first_success(&[MOZ_URL])
+			.and_then(|bytes| Self::from_lines(&utf16le(&bytes)))
+			.or_else(|| {
+				data.get_first_success(&[NETSCAPE_URL, X_URI])
+					.and_then(|bytes| Self::from_lines(&String::from_utf8_lossy(&bytes)))
+			})
+			.or_else(|| {
+				data.get_first_success(&[URL_W])
+					.and_then(|bytes| Self::from_lines(&utf16le(&bytes)))
+			})
+			.or_else(|| {
+				data.get_first_success(&[URL_A])
+					.and_then(|bytes| Self::from_lines(&decode_text("CF_TEXT", &bytes)?))
+			});
+		if parsed.is_some() {
+			return parsed;
+		}
+
+		let text = data.read_data::<String>()?;
+		let text = text.trim();
+		is_url(text).then(|| Self {
+			url: text.to_string(),
+			title: None,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_url_and_title() {
+		let bytes: Vec<u8> = "https://example.com/\nExample Domain"
+			.encode_utf16()
+			.flat_map(u16::to_le_bytes)
+			.collect();
+
+		assert_eq!(
+			ClipboardUrl::from_lines(&utf16le(&bytes)),
+			Some(ClipboardUrl {
+				url: "https://example.com/".to_string(),
+				title: Some("Example Domain".to_string()),
+			})
+		);
+	}
+
+	#[test]
+	fn validates_urls() {
+		assert!(is_url("https://example.com/a?b#c"));
+		assert!(is_url("mailto:someone@example.com"));
+		assert!(is_url("file:///home/user"));
+		assert!(!is_url("https://"));
+		assert!(!is_url("just some text"));
+		assert!(!is_url("see https://example.com"));
+		assert!(!is_url("C:\\folder"));
+		assert!(!is_url("note: remember"));
+	}
+
+	#[test]
+	fn reads_ansi_urls() {
+		let snapshot = crate::ClipboardSnapshot::new(vec![(
+			URL_A.to_string(),
+			b"https://example.com/caf\xe9\0garbage".to_vec(),
+		)]);
+
+		assert!(snapshot.is_available::<ClipboardUrl>());
+		assert_eq!(
+			snapshot.read_data::<ClipboardUrl>(),
+			Some(ClipboardUrl {
+				url: "https://example.com/café".to_string(),
+				title: None,
+			})
+		);
+	}
+}
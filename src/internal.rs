@@ -1,6 +1,11 @@
+#[cfg(feature = "unstable_write")]
+use std::time::Duration;
+
 use raw_window_handle::HasDisplayHandle;
 
-use crate::{ClipboardError, ClipboardHandler};
+#[cfg(feature = "unstable_write")]
+use crate::WriteToClipboard;
+use crate::{ClipboardBackend, ClipboardError, ClipboardHandler};
 
 pub(crate) trait InternalClipboard {
 	fn new<T: ClipboardHandler>(display_handle: &dyn HasDisplayHandle, handler: T) -> Self;
@@ -10,9 +15,20 @@ pub(crate) trait InternalClipboard {
 
 	#[cfg(feature = "unstable_write")]
 	fn write<T: WriteToClipboard>(&self, data: T);
+
+	#[cfg(feature = "unstable_write")]
+	fn write_expiring<T: WriteToClipboard>(&self, data: T, timeout: Duration);
+
+	#[cfg(feature = "unstable_write")]
+	fn persist(&self);
+
+	#[cfg(feature = "unstable_write")]
+	fn write_persistent<T: WriteToClipboard>(&self, data: T) -> Result<(), ClipboardError>;
 }
 
 pub(crate) trait InternalDataAccess {
+	fn backend(&self) -> ClipboardBackend;
+
 	fn mime_types(&self) -> &[String];
 
 	fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError>;
@@ -1,10 +1,42 @@
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __derive;
+mod cf_html;
+mod classify;
 mod clipboard_error;
+mod data_access_handle;
+mod dib;
+#[cfg(feature = "history")]
+pub mod history;
 mod implementations;
 mod internal;
+pub mod mime_aliases;
+mod read_first;
+mod sensitive;
+mod snapshot;
+#[cfg(feature = "serde")]
+pub mod snapshot_dump;
 
+use std::collections::BTreeSet;
+#[cfg(feature = "unstable_write")]
+use std::time::Duration;
+
+pub use classify::ClipboardContentKind;
 pub use clipboard_error::*;
+pub use data_access_handle::DataAccessHandle;
+use data_access_handle::DataAccessHandleInternal;
+#[cfg(feature = "derive")]
+pub use gengine_clipboard_derive::{ReadFromClipboard, WriteToClipboard};
+pub use implementations::{
+	ClipboardContentBuilder, ClipboardFiles, ClipboardHtml, ClipboardImage, ClipboardUrl,
+	FileOperation, Rtf,
+};
+#[cfg(feature = "serde")]
+pub use implementations::{Codec, CustomData, CustomFormat, Migration};
 use internal::{InternalClipboard, InternalDataAccess};
 use raw_window_handle::HasDisplayHandle;
+pub use read_first::{OneOf2, OneOf3, OneOf4, OneOf5, OneOf6, ReadFirst, TypeReader};
+pub use snapshot::{ClipboardSnapshot, SnapshotOrigin};
 
 #[cfg(not(target_arch = "wasm32"))]
 pub trait WasmOrSend: Send {}
@@ -18,7 +50,7 @@ pub trait WasmOrSend {}
 #[cfg(target_arch = "wasm32")]
 impl<T> WasmOrSend for T {}
 
-pub trait WriteToClipboard {
+pub trait WriteToClipboard: WasmOrSend + 'static {
 	fn viable_conversions(&self) -> Vec<String>;
 
 	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>>;
@@ -38,6 +70,22 @@ pub struct ClipboardEventSource {
 	pub(crate) value: usize,
 }
 
+/// The clipboard implementation some data originates from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "lowercase")
+)]
+pub enum ClipboardBackend {
+	X11,
+	Wayland,
+	Windows,
+	Web,
+	/// The data was created by the application and did not come from a clipboard.
+	Application,
+}
+
 pub enum ClipboardEvent<'a> {
 	StartedPasteHandling {
 		source: ClipboardEventSource,
@@ -50,6 +98,21 @@ pub enum ClipboardEvent<'a> {
 		data: &'a DataAccess,
 		source: ClipboardEventSource,
 	},
+	/// Another program, or this one, changed the clipboard content.
+	///
+	/// Not available on the web, as browsers do not report clipboard changes.
+	/// On Wayland, changes are only reported while a window of this program has keyboard focus.
+	ClipboardChanged {
+		data: &'a DataAccess,
+		source: ClipboardEventSource,
+	},
+	/// Content written with [`Clipboard::write_data_expiring`] was cleared, after its timeout ran
+	/// out.
+	///
+	/// Not reported, if another program replaced the content before.
+	Expired {
+		source: ClipboardEventSource,
+	},
 }
 
 pub trait ClipboardHandler: WasmOrSend + Sized + 'static {
@@ -61,20 +124,73 @@ pub trait ClipboardHandler: WasmOrSend + Sized + 'static {
 #[cfg_attr(target_arch = "wasm32", path = "wasm/mod.rs")]
 mod platform;
 
+/// Serves the content of [`Clipboard::write_persistent`], if this process was started for that.
+///
+/// Call this first thing in `main`. On Wayland, `write_persistent` starts the current executable
+/// again, which turns into the server here and never returns. Otherwise this returns right away.
+///
+/// ```ignore
+/// fn main() {
+///     gengine_clipboard::run_persistent_server_if_requested();
+///     // ...
+/// }
+/// ```
+#[cfg(feature = "unstable_write")]
+pub fn run_persistent_server_if_requested() {
+	platform::run_persistent_server_if_requested();
+}
+
 pub struct DataAccess {
-	internal: platform::DataAccess,
+	internal: DataAccessInternal,
+}
+
+pub(crate) enum DataAccessInternal {
+	Platform(platform::DataAccess),
+	Snapshot(ClipboardSnapshot),
+	Handle(DataAccessHandle),
 }
 
 impl DataAccess {
+	pub(crate) fn new(internal: platform::DataAccess) -> Self {
+		Self {
+			internal: DataAccessInternal::Platform(internal),
+		}
+	}
+
+	fn internal(&self) -> &dyn InternalDataAccess {
+		match &self.internal {
+			DataAccessInternal::Platform(internal) => internal,
+			DataAccessInternal::Snapshot(snapshot) => snapshot,
+			DataAccessInternal::Handle(handle) => handle.internal(),
+		}
+	}
+
+	/// Creates a [`DataAccessHandle`], which can be sent to another thread and read later.
+	///
+	/// On X11, every read of the handle first asks the selection owner for its timestamp, to
+	/// detect changes. That costs an extra round trip to the owner.
+	pub fn handle(&self) -> DataAccessHandle {
+		match &self.internal {
+			DataAccessInternal::Platform(internal) => DataAccessHandle {
+				internal: DataAccessHandleInternal::Platform(internal.handle()),
+			},
+			DataAccessInternal::Snapshot(snapshot) => DataAccessHandle {
+				internal: DataAccessHandleInternal::Snapshot(snapshot.clone()),
+			},
+			DataAccessInternal::Handle(handle) => handle.clone(),
+		}
+	}
+
 	pub fn raw_types(&self) -> Vec<&str> {
-		<platform::DataAccess as InternalDataAccess>::mime_types(&self.internal)
+		self.internal()
+			.mime_types()
 			.iter()
 			.map(|ty| ty.as_str())
 			.collect()
 	}
 
 	pub fn get_raw_data(&self, raw_type: &str) -> Result<Vec<u8>, ClipboardError> {
-		<platform::DataAccess as InternalDataAccess>::get_raw_data(&self.internal, raw_type)
+		self.internal().get_raw_data(raw_type)
 	}
 
 	pub fn is_available<T: ReadFromClipboard>(&self) -> bool {
@@ -85,6 +201,44 @@ impl DataAccess {
 		T::read(self)
 	}
 
+	/// Whether the source asked clipboard managers and histories not to store the content.
+	///
+	/// The hints of all platforms are checked, see [`Clipboard::write_data_sensitive`].
+	pub fn is_sensitive(&self) -> bool {
+		sensitive::is_sensitive(self)
+	}
+
+	/// Tells what kinds of content are offered, without fetching any data.
+	///
+	/// Only the raw types are looked at, so a source offering a format it cannot deliver still
+	/// counts.
+	pub fn classify(&self) -> BTreeSet<ClipboardContentKind> {
+		classify::classify(&self.raw_types())
+	}
+
+	/// Reads the first type of the tuple `T`, which is available and can be read.
+	///
+	/// ```ignore
+	/// match data.read_first::<(ClipboardImage, ClipboardFiles, String)>() {
+	///     Some(OneOf3::A(image)) => {}
+	///     Some(OneOf3::B(files)) => {}
+	///     Some(OneOf3::C(text)) => {}
+	///     None => {}
+	/// }
+	/// ```
+	pub fn read_first<T: ReadFirst>(&self) -> Option<T::Output> {
+		read_first::read_first::<T>(self)
+	}
+
+	/// Like [`DataAccess::read_first`], but follows the order of [`DataAccess::raw_types`],
+	/// which is the preference of the source.
+	///
+	/// A type is tried at the first raw type it accepts, and then reads whichever of its formats
+	/// it prefers.
+	pub fn read_preferred<T: ReadFirst>(&self) -> Option<T::Output> {
+		read_first::read_preferred::<T>(self)
+	}
+
 	pub fn get_first_success(&self, raw_types: &[&str]) -> Option<Vec<u8>> {
 		let available = self.raw_types();
 		for raw_type in raw_types {
@@ -97,6 +251,60 @@ impl DataAccess {
 
 		None
 	}
+
+	/// Returns the offered formats under their canonical mime types, see [`mime_aliases`].
+	///
+	/// Formats offered under several aliases are only listed once.
+	pub fn canonical_types(&self) -> Vec<String> {
+		let mut canonical_types: Vec<String> = Vec::new();
+		for raw_type in self.raw_types() {
+			let canonical = mime_aliases::canonical_type(raw_type);
+			if !canonical_types.contains(&canonical) {
+				canonical_types.push(canonical);
+			}
+		}
+
+		canonical_types
+	}
+
+	/// Fetches `mime_type` under whichever of its aliases the source offers.
+	///
+	/// Aliases are tried in the order the source offered them.
+	pub fn get_canonical(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError> {
+		let names = mime_aliases::names_of(mime_type);
+		let mut result = Err(ClipboardError::FormatNotAvailable);
+		for raw_type in self.raw_types() {
+			if names.iter().any(|name| name == raw_type) {
+				result = self.get_raw_data(raw_type);
+				if result.is_ok() {
+					break;
+				}
+			}
+		}
+
+		result
+	}
+
+	/// Eagerly fetches the clipboard content into an owned [`ClipboardSnapshot`].
+	///
+	/// With `filter` set only the listed raw types are fetched, otherwise all of them are.
+	/// Formats that fail to be fetched are left out of the snapshot. X11 targets like `TARGETS`
+	/// or `DELETE`, which are not content, are never fetched.
+	pub fn snapshot(&self, filter: Option<&[&str]>) -> ClipboardSnapshot {
+		let entries = self
+			.raw_types()
+			.into_iter()
+			.filter(|raw_type| !classify::SELECTION_TARGETS.contains(raw_type))
+			.filter(|raw_type| filter.is_none_or(|filter| filter.contains(raw_type)))
+			.filter_map(|raw_type| {
+				self.get_raw_data(raw_type)
+					.ok()
+					.map(|bytes| (raw_type.to_string(), bytes))
+			})
+			.collect();
+
+		ClipboardSnapshot::with_origin(entries, SnapshotOrigin::current(self.internal().backend()))
+	}
 }
 
 pub struct Clipboard {
@@ -122,4 +330,49 @@ impl Clipboard {
 	pub fn write_data<T: WriteToClipboard>(&self, data: T) {
 		self.internal.write(data);
 	}
+
+	/// Writes `data` with hints, which ask clipboard managers and histories not to store it.
+	///
+	/// That is `x-kde-passwordManagerHint` on Linux, and on Windows the formats keeping it out of
+	/// clipboard monitoring, the clipboard history and the cloud clipboard. Programs ignoring the
+	/// hints still see the content.
+	#[cfg(feature = "unstable_write")]
+	pub fn write_data_sensitive<T: WriteToClipboard>(&self, data: T) {
+		self.internal.write(sensitive::Sensitive(data));
+	}
+
+	/// Writes `data` and clears the clipboard after `timeout`, which is reported as
+	/// [`ClipboardEvent::Expired`].
+	///
+	/// The clipboard is only cleared, while it still holds our content. A later write of this
+	/// [`Clipboard`] cancels the timeout. Clipboard managers, which copied the content, still keep
+	/// it.
+	#[cfg(feature = "unstable_write")]
+	pub fn write_data_expiring<T: WriteToClipboard>(&self, data: T, timeout: Duration) {
+		self.internal.write_expiring(data, timeout);
+	}
+
+	/// Hands the written content to the clipboard manager, so it stays available after the
+	/// program exits. This also happens when the [`Clipboard`] is dropped.
+	///
+	/// On X11 this times out if no clipboard manager is running. On Windows this renders the
+	/// formats, which are otherwise only rendered once a paste target asks for them.
+	#[cfg(feature = "unstable_write")]
+	pub fn persist(&self) {
+		self.internal.persist();
+	}
+
+	/// Writes `data`, so it stays available after the program exits.
+	///
+	/// On Wayland all formats are converted right away and served by a detached process, until
+	/// another client takes the clipboard. This needs a compositor supporting the
+	/// `ext-data-control` or `wlr-data-control` protocol, and a call to
+	/// [`run_persistent_server_if_requested`] at the top of `main`. Without that call, this fails
+	/// with [`ClipboardError::Unsupported`]. On the web this fails with
+	/// [`ClipboardError::Unsupported`]. Elsewhere this is a [`Clipboard::write_data`] followed by
+	/// [`Clipboard::persist`].
+	#[cfg(feature = "unstable_write")]
+	pub fn write_persistent<T: WriteToClipboard>(&self, data: T) -> Result<(), ClipboardError> {
+		self.internal.write_persistent(data)
+	}
 }
@@ -6,23 +6,56 @@ use std::rc::Rc;
 use raw_window_handle::{HasDisplayHandle, RawDisplayHandle};
 
 use crate::{
-	ClipboardHandler, InternalClipboard,
+	ClipboardBackend, ClipboardHandler, InternalClipboard,
 	internal::InternalDataAccess,
 	platform::{
-		wayland::{WaylandClipboard, WaylandPasteDataAccess},
-		x11::{ConnectionHandler, X11Clipboard},
+		wayland::{WaylandClipboard, WaylandDataAccessHandle, WaylandPasteDataAccess},
+		x11::{
+			CommandSender, ConnectionHandler, SelectionIdentity, X11Clipboard, X11DataAccessHandle,
+		},
 	},
 };
 
+#[cfg(feature = "unstable_write")]
+pub use wayland::run_persistent_server_if_requested;
+
 pub enum DataAccess {
 	X11 {
 		conn: Rc<ConnectionHandler>,
 		mime_types: Vec<String>,
+		/// The content `mime_types` belong to.
+		identity: SelectionIdentity,
+		sender: CommandSender,
 	},
 	Wayland(WaylandPasteDataAccess),
 }
 
+impl DataAccess {
+	pub fn handle(&self) -> DataAccessHandle {
+		match self {
+			DataAccess::X11 {
+				mime_types,
+				identity,
+				sender,
+				..
+			} => DataAccessHandle::X11(X11DataAccessHandle::new(
+				mime_types.clone(),
+				*identity,
+				sender.clone(),
+			)),
+			DataAccess::Wayland(data_access) => DataAccessHandle::Wayland(data_access.handle()),
+		}
+	}
+}
+
 impl InternalDataAccess for DataAccess {
+	fn backend(&self) -> ClipboardBackend {
+		match self {
+			DataAccess::X11 { .. } => ClipboardBackend::X11,
+			DataAccess::Wayland(_) => ClipboardBackend::Wayland,
+		}
+	}
+
 	fn mime_types(&self) -> &[String] {
 		match self {
 			DataAccess::X11 { mime_types, .. } => mime_types,
@@ -38,6 +71,35 @@ impl InternalDataAccess for DataAccess {
 	}
 }
 
+#[derive(Clone)]
+pub enum DataAccessHandle {
+	X11(X11DataAccessHandle),
+	Wayland(WaylandDataAccessHandle),
+}
+
+impl InternalDataAccess for DataAccessHandle {
+	fn backend(&self) -> ClipboardBackend {
+		match self {
+			DataAccessHandle::X11(_) => ClipboardBackend::X11,
+			DataAccessHandle::Wayland(_) => ClipboardBackend::Wayland,
+		}
+	}
+
+	fn mime_types(&self) -> &[String] {
+		match self {
+			DataAccessHandle::X11(handle) => handle.mime_types(),
+			DataAccessHandle::Wayland(handle) => handle.mime_types(),
+		}
+	}
+
+	fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, crate::ClipboardError> {
+		match self {
+			DataAccessHandle::X11(handle) => handle.get_raw_data(mime_type),
+			DataAccessHandle::Wayland(handle) => handle.get_raw_data(mime_type),
+		}
+	}
+}
+
 pub enum Internal {
 	X11(X11Clipboard),
 	Wayland(WaylandClipboard),
@@ -79,7 +141,49 @@ impl InternalClipboard for Clipboard {
 	}
 
 	#[cfg(feature = "unstable_write")]
-	fn write(&self, data: crate::ClipboardData) {
-		todo!()
+	fn write<T: crate::WriteToClipboard>(&self, data: T) {
+		match &self.internal {
+			Internal::X11(internal) => {
+				internal.write(data);
+			}
+			Internal::Wayland(internal) => {
+				internal.write(data);
+			}
+		}
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_expiring<T: crate::WriteToClipboard>(&self, data: T, timeout: std::time::Duration) {
+		match &self.internal {
+			Internal::X11(internal) => {
+				internal.write_expiring(data, timeout);
+			}
+			Internal::Wayland(internal) => {
+				internal.write_expiring(data, timeout);
+			}
+		}
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_persistent<T: crate::WriteToClipboard>(
+		&self,
+		data: T,
+	) -> Result<(), crate::ClipboardError> {
+		match &self.internal {
+			Internal::X11(internal) => internal.write_persistent(data),
+			Internal::Wayland(internal) => internal.write_persistent(data),
+		}
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn persist(&self) {
+		match &self.internal {
+			Internal::X11(internal) => {
+				internal.persist();
+			}
+			Internal::Wayland(internal) => {
+				internal.persist();
+			}
+		}
 	}
 }
@@ -1,16 +1,24 @@
+#[cfg(feature = "unstable_write")]
+use sctk::{
+	data_device_manager::data_source::CopyPasteSource,
+	reexports::{
+		calloop::timer::{TimeoutAction, Timer},
+		client::protocol::wl_data_source::WlDataSource,
+	},
+};
 use sctk::{
 	data_device_manager::{
-		DataDeviceManagerState,
+		DataDeviceManagerState, ReadPipe,
 		data_device::{DataDevice, DataDeviceHandler},
-		data_offer::DataOfferHandler,
+		data_offer::{DataOfferHandler, SelectionOffer},
 		data_source::DataSourceHandler,
 	},
 	delegate_data_device, delegate_pointer, delegate_registry, delegate_seat, delegate_touch,
 	reexports::{
-		calloop::LoopHandle,
+		calloop::{LoopHandle, channel::Sender},
 		calloop_wayland_source::WaylandSource,
 		client::{
-			Connection, Dispatch, Proxy,
+			Connection, Dispatch, Proxy, QueueHandle,
 			backend::{Backend, ObjectId},
 			globals::registry_queue_init,
 			protocol::{
@@ -29,11 +37,16 @@ use sctk::{
 	},
 };
 use std::collections::HashMap;
+#[cfg(feature = "unstable_write")]
+use std::{io::Write, thread, time::Duration};
 
+#[cfg(feature = "unstable_write")]
+use crate::WriteToClipboard;
 use crate::{
 	ClipboardError, ClipboardEventSource, ClipboardHandler,
 	platform::wayland::{
-		even_handler_thread::HandlerThread, paste_data_access::WaylandPasteDataAccess,
+		ThreadCommand, even_handler_thread::HandlerThread,
+		paste_data_access::WaylandPasteDataAccess,
 	},
 };
 
@@ -61,14 +74,32 @@ impl Drop for SeatCapabilities {
 	}
 }
 
+/// Data offered to other clients while our data source is the selection.
+#[cfg(feature = "unstable_write")]
+struct CopySource {
+	source: CopyPasteSource,
+	data: Box<dyn WriteToClipboard>,
+}
+
 pub struct WaylandHandler {
 	registry_state: RegistryState,
 	seat_state: SeatState,
 	data_device_manager_state: Option<DataDeviceManagerState>,
 	seats: HashMap<ObjectId, SeatCapabilities>,
 	latest_seat: Option<ObjectId>,
+	/// Serial of the latest input event, which is needed to set the selection.
+	#[cfg_attr(not(feature = "unstable_write"), allow(dead_code))]
+	latest_serial: Option<u32>,
+	#[cfg_attr(not(feature = "unstable_write"), allow(dead_code))]
+	queue_handle: QueueHandle<Self>,
+	#[cfg(feature = "unstable_write")]
+	copy_source: Option<CopySource>,
+	/// Needed to start the timers of expiring writes.
+	#[cfg(feature = "unstable_write")]
+	loop_handle: LoopHandle<'static, Self>,
 	even_count: usize,
 	handler: HandlerThread,
+	sender: Sender<ThreadCommand>,
 	pub exit: bool,
 }
 
@@ -77,13 +108,14 @@ impl WaylandHandler {
 		backend: Backend,
 		loop_handle: LoopHandle<'static, Self>,
 		handler: T,
+		sender: Sender<ThreadCommand>,
 	) -> Self {
 		let connection = Connection::from_backend(backend);
 		let (globals, event_queue) = registry_queue_init::<WaylandHandler>(&connection).unwrap();
-		let queue_handle = &event_queue.handle();
+		let queue_handle = event_queue.handle();
 
-		let data_device_manager_state = DataDeviceManagerState::bind(&globals, queue_handle).ok();
-		let seat_state = SeatState::new(&globals, queue_handle);
+		let data_device_manager_state = DataDeviceManagerState::bind(&globals, &queue_handle).ok();
+		let seat_state = SeatState::new(&globals, &queue_handle);
 
 		#[allow(clippy::mutable_key_type)]
 		let mut seats = HashMap::new();
@@ -92,7 +124,7 @@ impl WaylandHandler {
 		}
 
 		WaylandSource::new(connection, event_queue)
-			.insert(loop_handle)
+			.insert(loop_handle.clone())
 			.unwrap();
 
 		let handler = HandlerThread::new(handler);
@@ -103,8 +135,15 @@ impl WaylandHandler {
 			data_device_manager_state,
 			seats,
 			latest_seat: None,
+			latest_serial: None,
+			queue_handle,
+			#[cfg(feature = "unstable_write")]
+			copy_source: None,
+			#[cfg(feature = "unstable_write")]
+			loop_handle,
 			exit: false,
 			handler,
+			sender,
 			even_count: 0,
 		}
 	}
@@ -157,9 +196,105 @@ impl WaylandHandler {
 			}
 		};
 
-		let data = WaylandPasteDataAccess::new(selection);
+		let data = WaylandPasteDataAccess::new(selection, self.sender.clone());
 		self.handler.paste_result(data, source);
 	}
+
+	/// Starts receiving data from an offer, which must still be the selection of a seat.
+	pub fn receive(
+		&self,
+		offer: &SelectionOffer,
+		mime_type: String,
+	) -> Result<ReadPipe, ClipboardError> {
+		let is_selection = self
+			.seats
+			.values()
+			.filter_map(|seat| seat.data_device.as_ref())
+			.any(|data_device| data_device.data().selection_offer().as_ref() == Some(offer));
+
+		if !is_selection {
+			return Err(ClipboardError::Stale);
+		}
+
+		offer.receive(mime_type).map_err(|_| {
+			ClipboardError::Unknown(
+				"selection does not want to give after offering wayland".to_string(),
+			)
+		})
+	}
+
+	#[cfg(feature = "unstable_write")]
+	pub fn write(&mut self, data: Box<dyn WriteToClipboard>, timeout: Option<Duration>) {
+		let Some(manager) = self.data_device_manager_state.as_ref() else {
+			log::error!("Failed to write to the clipboard: no data device manager in wayland.");
+			return;
+		};
+
+		let Some(serial) = self.latest_serial else {
+			log::error!("Failed to write to the clipboard: no input event received yet.");
+			return;
+		};
+
+		let data_device = self
+			.latest_seat
+			.as_ref()
+			.and_then(|latest| self.seats.get(latest))
+			.and_then(|seat| seat.data_device.as_ref());
+		let Some(data_device) = data_device else {
+			log::error!("Failed to write to the clipboard: no data device in wayland.");
+			return;
+		};
+
+		let source =
+			manager.create_copy_paste_source(&self.queue_handle, data.viable_conversions());
+		source.set_selection(data_device, serial);
+
+		if let Some(timeout) = timeout {
+			let expiring = source.inner().clone();
+			let inserted = self.loop_handle.insert_source(
+				Timer::from_duration(timeout),
+				move |_, _, state| {
+					state.expire(&expiring);
+					TimeoutAction::Drop
+				},
+			);
+			if inserted.is_err() {
+				log::error!("Failed to start the timer of an expiring write in wayland.");
+			}
+		}
+
+		self.copy_source = Some(CopySource { source, data });
+	}
+
+	/// Clears the selection, if `source` was not cancelled or replaced by another write.
+	#[cfg(feature = "unstable_write")]
+	fn expire(&mut self, source: &WlDataSource) {
+		if !self
+			.copy_source
+			.as_ref()
+			.is_some_and(|copy_source| copy_source.source.inner() == source)
+		{
+			return;
+		}
+
+		let data_device = self
+			.latest_seat
+			.as_ref()
+			.and_then(|latest| self.seats.get(latest))
+			.and_then(|seat| seat.data_device.as_ref());
+		if let (Some(data_device), Some(serial)) = (data_device, self.latest_serial) {
+			data_device.unset_selection(serial);
+		}
+
+		// Destroying the source clears the selection as well, in case the serial was refused.
+		self.copy_source = None;
+
+		let source = ClipboardEventSource {
+			value: self.even_count,
+		};
+		self.even_count += 1;
+		self.handler.expired(source);
+	}
 }
 
 impl SeatHandler for WaylandHandler {
@@ -292,8 +427,27 @@ impl DataDeviceHandler for WaylandHandler {
 		&mut self,
 		_conn: &Connection,
 		_qh: &sctk::reexports::client::QueueHandle<Self>,
-		_data_device: &sctk::reexports::client::protocol::wl_data_device::WlDataDevice,
+		data_device: &sctk::reexports::client::protocol::wl_data_device::WlDataDevice,
 	) {
+		let selection = self
+			.seats
+			.values()
+			.filter_map(|seat| seat.data_device.as_ref())
+			.find(|device| device.inner() == data_device)
+			.and_then(|device| device.data().selection_offer());
+
+		// A cleared selection is nothing to report.
+		let Some(selection) = selection else {
+			return;
+		};
+
+		let source = ClipboardEventSource {
+			value: self.even_count,
+		};
+		self.even_count += 1;
+
+		let data = WaylandPasteDataAccess::new(selection, self.sender.clone());
+		self.handler.clipboard_changed(data, source);
 	}
 }
 
@@ -318,14 +472,26 @@ impl DataOfferHandler for WaylandHandler {
 }
 
 impl DataSourceHandler for WaylandHandler {
+	#[cfg_attr(not(feature = "unstable_write"), allow(unused_variables))]
 	fn send_request(
 		&mut self,
 		_conn: &Connection,
 		_qh: &sctk::reexports::client::QueueHandle<Self>,
-		_source: &sctk::reexports::client::protocol::wl_data_source::WlDataSource,
-		_mime: String,
-		_fd: sctk::data_device_manager::WritePipe,
+		source: &sctk::reexports::client::protocol::wl_data_source::WlDataSource,
+		mime: String,
+		fd: sctk::data_device_manager::WritePipe,
 	) {
+		#[cfg(feature = "unstable_write")]
+		if let Some(copy_source) = self.copy_source.as_ref()
+			&& copy_source.source.inner() == source
+			&& let Some(bytes) = copy_source.data.convert_to(&mime)
+		{
+			// Writing happens on another thread, so a slow reader does not block the event loop.
+			let mut fd = fd;
+			thread::spawn(move || {
+				let _ = fd.write_all(&bytes);
+			});
+		}
 	}
 
 	fn accept_mime(
@@ -354,12 +520,21 @@ impl DataSourceHandler for WaylandHandler {
 	) {
 	}
 
+	#[cfg_attr(not(feature = "unstable_write"), allow(unused_variables))]
 	fn cancelled(
 		&mut self,
 		_conn: &Connection,
 		_qh: &sctk::reexports::client::QueueHandle<Self>,
-		_source: &sctk::reexports::client::protocol::wl_data_source::WlDataSource,
+		source: &sctk::reexports::client::protocol::wl_data_source::WlDataSource,
 	) {
+		#[cfg(feature = "unstable_write")]
+		if self
+			.copy_source
+			.as_ref()
+			.is_some_and(|copy_source| copy_source.source.inner() == source)
+		{
+			self.copy_source = None;
+		}
 	}
 
 	fn dnd_finished(
@@ -389,8 +564,10 @@ impl Dispatch<WlKeyboard, ObjectId, WaylandHandler> for WaylandHandler {
 		_qhandle: &sctk::reexports::client::QueueHandle<WaylandHandler>,
 	) {
 		match event {
-			wl_keyboard::Event::Key { .. } | wl_keyboard::Event::Modifiers { .. } => {
+			wl_keyboard::Event::Key { serial, .. }
+			| wl_keyboard::Event::Modifiers { serial, .. } => {
 				state.latest_seat = Some(data.clone());
+				state.latest_serial = Some(serial);
 			}
 			_ => {}
 		}
@@ -403,7 +580,7 @@ impl TouchHandler for WaylandHandler {
 		_conn: &Connection,
 		_qh: &sctk::reexports::client::QueueHandle<Self>,
 		touch: &WlTouch,
-		_serial: u32,
+		serial: u32,
 		_time: u32,
 		_surface: sctk::reexports::client::protocol::wl_surface::WlSurface,
 		_id: i32,
@@ -411,6 +588,7 @@ impl TouchHandler for WaylandHandler {
 	) {
 		let seat = touch.data::<TouchData>().unwrap().seat();
 		self.latest_seat = Some(seat.id());
+		self.latest_serial = Some(serial);
 	}
 
 	fn up(
@@ -418,12 +596,13 @@ impl TouchHandler for WaylandHandler {
 		_conn: &Connection,
 		_qh: &sctk::reexports::client::QueueHandle<Self>,
 		touch: &WlTouch,
-		_serial: u32,
+		serial: u32,
 		_time: u32,
 		_id: i32,
 	) {
 		let seat = touch.data::<TouchData>().unwrap().seat();
 		self.latest_seat = Some(seat.id());
+		self.latest_serial = Some(serial);
 	}
 
 	fn cancel(
@@ -477,9 +656,11 @@ impl PointerHandler for WaylandHandler {
 	) {
 		for event in events {
 			match event.kind {
-				PointerEventKind::Press { .. } | PointerEventKind::Release { .. } => {
+				PointerEventKind::Press { serial, .. }
+				| PointerEventKind::Release { serial, .. } => {
 					let seat = pointer.data::<PointerData>().unwrap().seat();
 					self.latest_seat = Some(seat.id());
+					self.latest_serial = Some(serial);
 					return;
 				}
 				_ => (),
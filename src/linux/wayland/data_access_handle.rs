@@ -0,0 +1,59 @@
+use std::sync::mpsc;
+
+use sctk::{data_device_manager::data_offer::SelectionOffer, reexports::calloop::channel::Sender};
+
+use crate::{
+	ClipboardBackend, ClipboardError,
+	internal::InternalDataAccess,
+	platform::wayland::{ThreadCommand, paste_data_access::read_pipe},
+};
+
+#[derive(Clone)]
+pub struct WaylandDataAccessHandle {
+	mime_types: Vec<String>,
+	offer: SelectionOffer,
+	sender: Sender<ThreadCommand>,
+}
+
+impl WaylandDataAccessHandle {
+	pub fn new(
+		mime_types: Vec<String>,
+		offer: SelectionOffer,
+		sender: Sender<ThreadCommand>,
+	) -> Self {
+		Self {
+			mime_types,
+			offer,
+			sender,
+		}
+	}
+}
+
+impl InternalDataAccess for WaylandDataAccessHandle {
+	fn backend(&self) -> ClipboardBackend {
+		ClipboardBackend::Wayland
+	}
+
+	fn mime_types(&self) -> &[String] {
+		&self.mime_types
+	}
+
+	fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError> {
+		// The wayland thread only hands out the pipe, so reading does not block its event loop.
+		let (reply, receiver) = mpsc::channel();
+		let command = ThreadCommand::Receive {
+			offer: self.offer.clone(),
+			mime_type: mime_type.to_string(),
+			reply,
+		};
+
+		if self.sender.send(command).is_err() {
+			return Err(ClipboardError::ClipboardDataUnavailable);
+		}
+
+		let pipe = receiver
+			.recv()
+			.unwrap_or(Err(ClipboardError::ClipboardDataUnavailable))?;
+		read_pipe(pipe)
+	}
+}
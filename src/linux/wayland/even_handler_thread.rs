@@ -20,6 +20,14 @@ enum HandlerThreadCommand {
 		source: ClipboardEventSource,
 		data: WaylandPasteDataAccess,
 	},
+	ClipboardChanged {
+		source: ClipboardEventSource,
+		data: WaylandPasteDataAccess,
+	},
+	#[cfg(feature = "unstable_write")]
+	Expired {
+		source: ClipboardEventSource,
+	},
 	Exit,
 }
 
@@ -43,15 +51,24 @@ impl HandlerThread {
 					}
 					PasteResult { source, data } => {
 						let data_access = super::super::DataAccess::Wayland(data);
-						let data_access = DataAccess {
-							internal: data_access,
-						};
+						let data_access = DataAccess::new(data_access);
 
 						handler.handle_event(ClipboardEvent::PasteResult {
 							source,
 							data: &data_access,
 						});
 					}
+					ClipboardChanged { source, data } => {
+						let data_access = super::super::DataAccess::Wayland(data);
+						let data_access = DataAccess::new(data_access);
+
+						handler.handle_event(ClipboardEvent::ClipboardChanged {
+							source,
+							data: &data_access,
+						});
+					}
+					#[cfg(feature = "unstable_write")]
+					Expired { source } => handler.handle_event(ClipboardEvent::Expired { source }),
 					Exit => {
 						return;
 					}
@@ -82,6 +99,17 @@ impl HandlerThread {
 			.sender
 			.send(HandlerThreadCommand::PasteResult { source, data });
 	}
+
+	pub fn clipboard_changed(&self, data: WaylandPasteDataAccess, source: ClipboardEventSource) {
+		let _ = self
+			.sender
+			.send(HandlerThreadCommand::ClipboardChanged { source, data });
+	}
+
+	#[cfg(feature = "unstable_write")]
+	pub fn expired(&self, source: ClipboardEventSource) {
+		let _ = self.sender.send(HandlerThreadCommand::Expired { source });
+	}
 }
 
 impl Drop for HandlerThread {
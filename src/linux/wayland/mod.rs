@@ -1,10 +1,22 @@
 mod clipboard_handler;
+mod data_access_handle;
 mod even_handler_thread;
 mod paste_data_access;
+#[cfg(feature = "unstable_write")]
+mod persistent_source;
 
-use std::thread::{self, JoinHandle};
+#[cfg(feature = "unstable_write")]
+pub use persistent_source::run_persistent_server_if_requested;
+
+#[cfg(feature = "unstable_write")]
+use std::time::Duration;
+use std::{
+	sync::mpsc,
+	thread::{self, JoinHandle},
+};
 
 use raw_window_handle::RawDisplayHandle;
+use sctk::data_device_manager::{ReadPipe, data_offer::SelectionOffer};
 use sctk::reexports::{
 	calloop::{
 		EventLoop,
@@ -13,10 +25,14 @@ use sctk::reexports::{
 	client::backend::Backend,
 };
 
+#[cfg(feature = "unstable_write")]
+use crate::WriteToClipboard;
 use crate::{
-	ClipboardHandler, InternalClipboard, platform::wayland::clipboard_handler::WaylandHandler,
+	ClipboardError, ClipboardHandler, InternalClipboard,
+	platform::wayland::clipboard_handler::WaylandHandler,
 };
 
+pub use data_access_handle::WaylandDataAccessHandle;
 pub use paste_data_access::WaylandPasteDataAccess;
 
 pub struct WaylandClipboard {
@@ -26,6 +42,17 @@ pub struct WaylandClipboard {
 
 pub enum ThreadCommand {
 	RequestData,
+	Receive {
+		offer: SelectionOffer,
+		mime_type: String,
+		reply: mpsc::Sender<Result<ReadPipe, ClipboardError>>,
+	},
+	/// The selection is cleared after `timeout`, if it is set.
+	#[cfg(feature = "unstable_write")]
+	Write {
+		data: Box<dyn WriteToClipboard>,
+		timeout: Option<Duration>,
+	},
 	Exit,
 }
 
@@ -35,6 +62,7 @@ impl InternalClipboard for WaylandClipboard {
 		handler: T,
 	) -> Self {
 		let (sender, receiver) = channel::<ThreadCommand>();
+		let thread_sender = sender.clone();
 
 		let display_handle = window_handle.display_handle().unwrap();
 		let display = if let RawDisplayHandle::Wayland(handle) = display_handle.as_raw() {
@@ -54,14 +82,29 @@ impl InternalClipboard for WaylandClipboard {
 							ThreadCommand::RequestData => {
 								state.request_data();
 							}
+							ThreadCommand::Receive {
+								offer,
+								mime_type,
+								reply,
+							} => {
+								let _ = reply.send(state.receive(&offer, mime_type));
+							}
+							#[cfg(feature = "unstable_write")]
+							ThreadCommand::Write { data, timeout } => {
+								state.write(data, timeout);
+							}
 							ThreadCommand::Exit => state.exit = true,
 						}
 					}
 				})
 				.unwrap();
 
-			let mut wayland_handler =
-				WaylandHandler::create_and_insert(backend, loop_handle.clone(), handler);
+			let mut wayland_handler = WaylandHandler::create_and_insert(
+				backend,
+				loop_handle.clone(),
+				handler,
+				thread_sender,
+			);
 
 			loop {
 				if event_loop.dispatch(None, &mut wayland_handler).is_err() || wayland_handler.exit
@@ -82,8 +125,29 @@ impl InternalClipboard for WaylandClipboard {
 	}
 
 	#[cfg(feature = "unstable_write")]
-	fn write(&self, _data: ClipboardData) {
-		unimplemented!("Clipboard write not implemented yet.");
+	fn write<T: WriteToClipboard>(&self, data: T) {
+		let _ = self.sender.send(ThreadCommand::Write {
+			data: Box::new(data),
+			timeout: None,
+		});
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_expiring<T: WriteToClipboard>(&self, data: T, timeout: Duration) {
+		let _ = self.sender.send(ThreadCommand::Write {
+			data: Box::new(data),
+			timeout: Some(timeout),
+		});
+	}
+
+	/// Wayland has no clipboard manager handshake, compositors or clipboard managers copy
+	/// the offered data on their own.
+	#[cfg(feature = "unstable_write")]
+	fn persist(&self) {}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_persistent<T: WriteToClipboard>(&self, data: T) -> Result<(), ClipboardError> {
+		persistent_source::write_persistent(&data)
 	}
 }
 
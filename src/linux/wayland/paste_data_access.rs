@@ -1,30 +1,62 @@
-use sctk::data_device_manager::data_offer::SelectionOffer;
+use sctk::{
+	data_device_manager::{ReadPipe, data_offer::SelectionOffer},
+	reexports::calloop::channel::Sender,
+};
 use std::io::Read;
 
-use crate::{ClipboardError, internal::InternalDataAccess};
+use crate::{
+	ClipboardBackend, ClipboardError,
+	internal::InternalDataAccess,
+	platform::wayland::{ThreadCommand, WaylandDataAccessHandle},
+};
 
 pub struct WaylandPasteDataAccess {
 	mime_types: Vec<String>,
 	selection: SelectionOffer,
+	sender: Sender<ThreadCommand>,
 }
 
 impl WaylandPasteDataAccess {
-	pub fn new(selection: SelectionOffer) -> Self {
+	pub fn new(selection: SelectionOffer, sender: Sender<ThreadCommand>) -> Self {
 		let mime_types = selection.with_mime_types(|offers| offers.to_vec());
 		Self {
 			mime_types,
 			selection,
+			sender,
 		}
 	}
+
+	pub fn handle(&self) -> WaylandDataAccessHandle {
+		WaylandDataAccessHandle::new(
+			self.mime_types.clone(),
+			self.selection.clone(),
+			self.sender.clone(),
+		)
+	}
+}
+
+pub(super) fn read_pipe(mut read_pipe: ReadPipe) -> Result<Vec<u8>, ClipboardError> {
+	let mut buffer = Vec::new();
+	if read_pipe.read_to_end(&mut buffer).is_err() {
+		return Err(ClipboardError::Unknown(
+			"Failed to read clipboard content".to_string(),
+		));
+	}
+
+	Ok(buffer)
 }
 
 impl InternalDataAccess for WaylandPasteDataAccess {
+	fn backend(&self) -> ClipboardBackend {
+		ClipboardBackend::Wayland
+	}
+
 	fn mime_types(&self) -> &[String] {
 		&self.mime_types
 	}
 
 	fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError> {
-		let mut read_pipe = match self.selection.receive(mime_type.to_string()) {
+		let pipe = match self.selection.receive(mime_type.to_string()) {
 			Ok(read_pipe) => read_pipe,
 			_ => {
 				return Err(ClipboardError::Unknown(
@@ -33,13 +65,6 @@ impl InternalDataAccess for WaylandPasteDataAccess {
 			}
 		};
 
-		let mut buffer = Vec::new();
-		if read_pipe.read_to_end(&mut buffer).is_err() {
-			return Err(ClipboardError::Unknown(
-				"Failed to read clipboard content".to_string(),
-			));
-		}
-
-		Ok(buffer)
+		read_pipe(pipe)
 	}
 }
@@ -0,0 +1,457 @@
+//! Clipboard content, which stays available after the program exits.
+//!
+//! Wayland has no clipboard manager handshake, so a detached process keeps serving the content
+//! until another client takes the selection. Like `wl-copy`, the process uses a data control
+//! protocol, as it has no window and therefore no input serial to set the selection with.
+//!
+//! Forking a program with several threads only allows async-signal-safe calls in the child, so
+//! the server is a new instance of the current executable instead. It turns into the server in
+//! [`run_persistent_server_if_requested`], which the program calls at the top of `main`, and
+//! receives the converted content over its stdin.
+
+use std::{
+	env,
+	fs::{self, File},
+	io::{self, Read, Write},
+	os::fd::{AsRawFd, OwnedFd},
+	process::{self, ChildStdout, Command, Stdio},
+	sync::atomic::{AtomicBool, Ordering},
+	thread,
+	time::{Duration, Instant},
+};
+
+use sctk::reexports::{
+	client::{
+		Connection, Dispatch, QueueHandle, event_created_child,
+		globals::{GlobalListContents, registry_queue_init},
+		protocol::{wl_registry::WlRegistry, wl_seat::WlSeat},
+	},
+	protocols::ext::data_control::v1::client::{
+		ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+		ext_data_control_manager_v1::ExtDataControlManagerV1,
+		ext_data_control_offer_v1::ExtDataControlOfferV1,
+		ext_data_control_source_v1::{self, ExtDataControlSourceV1},
+	},
+	protocols_wlr::data_control::v1::client::{
+		zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+		zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+		zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
+		zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+	},
+};
+
+use crate::{ClipboardError, WriteToClipboard};
+
+/// Set in the environment of the server process.
+const SERVER_ENV: &str = "GENGINE_CLIPBOARD_PERSISTENT_SERVER";
+
+/// How long the server process may take to receive the content and take the selection.
+const TIMEOUT_LIMIT: Duration = Duration::from_secs(2);
+
+/// Whether the program calls [`run_persistent_server_if_requested`], so its executable can be
+/// started as the server.
+static SERVER_ENTRY: AtomicBool = AtomicBool::new(false);
+
+/// Status the server process reports back, once it tried to take the selection.
+const STATUS_SERVING: u8 = 0;
+const STATUS_CONNECT_FAILED: u8 = 1;
+const STATUS_UNSUPPORTED: u8 = 2;
+const STATUS_READ_FAILED: u8 = 3;
+
+/// Turns this process into the server, if it was started as one, and never returns then.
+pub fn run_persistent_server_if_requested() {
+	if env::var_os(SERVER_ENV).is_some() {
+		serve();
+	}
+	SERVER_ENTRY.store(true, Ordering::Relaxed);
+}
+
+/// Converts `data` to all its formats and serves them from a detached process.
+///
+/// Returns once the process owns the selection. The server is killed, if it does not report
+/// back within [`TIMEOUT_LIMIT`].
+pub fn write_persistent(data: &dyn WriteToClipboard) -> Result<(), ClipboardError> {
+	// Any other executable, like the interpreter a cdylib was loaded into, would run the content.
+	if !SERVER_ENTRY.load(Ordering::Relaxed) {
+		log::error!(
+			"write_persistent needs a call to run_persistent_server_if_requested at the top of main"
+		);
+		return Err(ClipboardError::Unsupported);
+	}
+
+	let entries: Vec<(String, Vec<u8>)> = data
+		.viable_conversions()
+		.into_iter()
+		.filter_map(|mime_type| data.convert_to(&mime_type).map(|bytes| (mime_type, bytes)))
+		.collect();
+
+	if entries.is_empty() {
+		return Err(ClipboardError::Empty);
+	}
+
+	let executable = env::current_exe().map_err(|_| ClipboardError::WriteFailed)?;
+	let mut server = Command::new(executable)
+		.env(SERVER_ENV, "1")
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::null())
+		.spawn()
+		.map_err(|_| ClipboardError::WriteFailed)?;
+
+	// Written from another thread, so a server which does not read cannot block the caller. It
+	// reports entries, which did not arrive completely.
+	if let Some(stdin) = server.stdin.take() {
+		thread::spawn(move || write_entries(stdin, &entries));
+	}
+	let status = match server.stdout.take() {
+		Some(mut stdout) => read_status(&mut stdout, Instant::now() + TIMEOUT_LIMIT),
+		None => Err(io::ErrorKind::BrokenPipe.into()),
+	};
+	if status.is_err() {
+		let _ = server.kill();
+	}
+
+	// Reaps the server once it stops serving, so it does not linger as a zombie.
+	thread::spawn(move || server.wait());
+
+	match status {
+		Ok(STATUS_SERVING) => Ok(()),
+		Ok(STATUS_CONNECT_FAILED) => Err(ClipboardError::OpenFailed),
+		Ok(STATUS_UNSUPPORTED) => Err(ClipboardError::SetFailed),
+		Err(error) if error.kind() == io::ErrorKind::TimedOut => Err(ClipboardError::Timeout),
+		_ => Err(ClipboardError::WriteFailed),
+	}
+}
+
+/// Waits until `deadline` for the status byte of the server.
+fn read_status(stdout: &mut ChildStdout, deadline: Instant) -> io::Result<u8> {
+	let mut fd = libc::pollfd {
+		fd: stdout.as_raw_fd(),
+		events: libc::POLLIN,
+		revents: 0,
+	};
+
+	loop {
+		let timeout = deadline.saturating_duration_since(Instant::now());
+		// Rounded up, so the last millisecond is waited for as well.
+		let timeout = timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32;
+		match unsafe { libc::poll(&mut fd, 1, timeout) } {
+			0 => return Err(io::ErrorKind::TimedOut.into()),
+			ready if ready > 0 => break,
+			_ => {
+				let error = io::Error::last_os_error();
+				if error.kind() != io::ErrorKind::Interrupted {
+					return Err(error);
+				}
+			}
+		}
+	}
+
+	let mut status = [0];
+	stdout.read_exact(&mut status)?;
+	Ok(status[0])
+}
+
+/// Writes the number of entries, followed by the length prefixed mime type and bytes of each.
+fn write_entries(mut writer: impl Write, entries: &[(String, Vec<u8>)]) -> io::Result<()> {
+	writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+	for (mime_type, bytes) in entries {
+		writer.write_all(&(mime_type.len() as u32).to_le_bytes())?;
+		writer.write_all(mime_type.as_bytes())?;
+		writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+		writer.write_all(bytes)?;
+	}
+	writer.flush()
+}
+
+fn read_entries(mut reader: impl Read) -> io::Result<Vec<(String, Vec<u8>)>> {
+	let count = read_length(&mut reader, 4)?;
+	(0..count)
+		.map(|_| {
+			let length = read_length(&mut reader, 4)?;
+			let mime_type = String::from_utf8(read_bytes(&mut reader, length)?)
+				.map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+			let length = read_length(&mut reader, 8)?;
+			Ok((mime_type, read_bytes(&mut reader, length)?))
+		})
+		.collect()
+}
+
+fn read_bytes(reader: &mut impl Read, length: usize) -> io::Result<Vec<u8>> {
+	let mut bytes = vec![0; length];
+	reader.read_exact(&mut bytes)?;
+	Ok(bytes)
+}
+
+/// Reads a little endian length of `size` bytes.
+fn read_length(reader: &mut impl Read, size: usize) -> io::Result<usize> {
+	let mut length = [0; 8];
+	reader.read_exact(&mut length[..size])?;
+	usize::try_from(u64::from_le_bytes(length)).map_err(|_| io::ErrorKind::InvalidData.into())
+}
+
+/// Runs in the server process and never returns.
+///
+/// Nothing is logged, as the server has no output anymore once it detached.
+fn serve() -> ! {
+	unsafe { libc::setsid() };
+	close_inherited_fds();
+
+	let report = |code: u8| {
+		let mut stdout = io::stdout();
+		let _ = stdout.write_all(&[code]).and_then(|_| stdout.flush());
+	};
+
+	let Ok(entries) = read_entries(io::stdin().lock()) else {
+		report(STATUS_READ_FAILED);
+		process::exit(1);
+	};
+
+	let Ok(conn) = Connection::connect_to_env() else {
+		report(STATUS_CONNECT_FAILED);
+		process::exit(1);
+	};
+	let Ok((globals, mut queue)) = registry_queue_init::<PersistentSource>(&conn) else {
+		report(STATUS_CONNECT_FAILED);
+		process::exit(1);
+	};
+	let qh = queue.handle();
+
+	let Ok(seat) = globals.bind::<WlSeat, _, _>(&qh, 1..=1, ()) else {
+		report(STATUS_UNSUPPORTED);
+		process::exit(1);
+	};
+
+	if let Ok(manager) = globals.bind::<ExtDataControlManagerV1, _, _>(&qh, 1..=1, ()) {
+		let source = manager.create_data_source(&qh, ());
+		for (mime_type, _) in &entries {
+			source.offer(mime_type.clone());
+		}
+		manager
+			.get_data_device(&seat, &qh, ())
+			.set_selection(Some(&source));
+	} else if let Ok(manager) = globals.bind::<ZwlrDataControlManagerV1, _, _>(&qh, 1..=1, ()) {
+		let source = manager.create_data_source(&qh, ());
+		for (mime_type, _) in &entries {
+			source.offer(mime_type.clone());
+		}
+		manager
+			.get_data_device(&seat, &qh, ())
+			.set_selection(Some(&source));
+	} else {
+		report(STATUS_UNSUPPORTED);
+		process::exit(1);
+	}
+
+	let mut state = PersistentSource {
+		entries,
+		cancelled: false,
+	};
+
+	if queue.roundtrip(&mut state).is_err() {
+		report(STATUS_CONNECT_FAILED);
+		process::exit(1);
+	}
+	report(STATUS_SERVING);
+	detach_stdio();
+
+	while !state.cancelled {
+		if queue.blocking_dispatch(&mut state).is_err() {
+			break;
+		}
+	}
+
+	process::exit(0);
+}
+
+/// Closes everything inherited from the parent except stdio, so its connections close when it
+/// exits.
+fn close_inherited_fds() {
+	let fds: Vec<i32> = match fs::read_dir("/proc/self/fd") {
+		Ok(entries) => entries
+			.filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+			.collect(),
+		Err(_) => return,
+	};
+
+	for fd in fds {
+		if fd > 2 {
+			unsafe { libc::close(fd) };
+		}
+	}
+}
+
+/// Points stdio to `/dev/null`, so the parent sees its pipes close.
+fn detach_stdio() {
+	let Ok(null) = File::options().read(true).write(true).open("/dev/null") else {
+		return;
+	};
+	let null = OwnedFd::from(null);
+	for fd in 0..3 {
+		unsafe { libc::dup2(null.as_raw_fd(), fd) };
+	}
+}
+
+struct PersistentSource {
+	entries: Vec<(String, Vec<u8>)>,
+	cancelled: bool,
+}
+
+impl PersistentSource {
+	fn send(&self, mime_type: &str, fd: OwnedFd) {
+		if let Some((_, bytes)) = self.entries.iter().find(|(ty, _)| ty == mime_type) {
+			let _ = File::from(fd).write_all(bytes);
+		}
+	}
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for PersistentSource {
+	fn event(
+		_: &mut Self,
+		_: &WlRegistry,
+		_: <WlRegistry as sctk::reexports::client::Proxy>::Event,
+		_: &GlobalListContents,
+		_: &Connection,
+		_: &QueueHandle<Self>,
+	) {
+	}
+}
+
+/// Objects, whose events are of no interest to the server.
+macro_rules! ignore_events {
+	($($interface:ty),*) => {$(
+		impl Dispatch<$interface, ()> for PersistentSource {
+			fn event(
+				_: &mut Self,
+				_: &$interface,
+				_: <$interface as sctk::reexports::client::Proxy>::Event,
+				_: &(),
+				_: &Connection,
+				_: &QueueHandle<Self>,
+			) {
+			}
+		}
+	)*};
+}
+
+ignore_events!(
+	WlSeat,
+	ExtDataControlManagerV1,
+	ExtDataControlOfferV1,
+	ZwlrDataControlManagerV1,
+	ZwlrDataControlOfferV1
+);
+
+impl Dispatch<ExtDataControlDeviceV1, ()> for PersistentSource {
+	fn event(
+		_: &mut Self,
+		_: &ExtDataControlDeviceV1,
+		_: ext_data_control_device_v1::Event,
+		_: &(),
+		_: &Connection,
+		_: &QueueHandle<Self>,
+	) {
+	}
+
+	event_created_child!(PersistentSource, ExtDataControlDeviceV1, [
+		ext_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ExtDataControlOfferV1, ()),
+	]);
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for PersistentSource {
+	fn event(
+		_: &mut Self,
+		_: &ZwlrDataControlDeviceV1,
+		_: zwlr_data_control_device_v1::Event,
+		_: &(),
+		_: &Connection,
+		_: &QueueHandle<Self>,
+	) {
+	}
+
+	event_created_child!(PersistentSource, ZwlrDataControlDeviceV1, [
+		zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+	]);
+}
+
+impl Dispatch<ExtDataControlSourceV1, ()> for PersistentSource {
+	fn event(
+		state: &mut Self,
+		_: &ExtDataControlSourceV1,
+		event: ext_data_control_source_v1::Event,
+		_: &(),
+		_: &Connection,
+		_: &QueueHandle<Self>,
+	) {
+		match event {
+			ext_data_control_source_v1::Event::Send { mime_type, fd } => state.send(&mime_type, fd),
+			ext_data_control_source_v1::Event::Cancelled => state.cancelled = true,
+			_ => {}
+		}
+	}
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, ()> for PersistentSource {
+	fn event(
+		state: &mut Self,
+		_: &ZwlrDataControlSourceV1,
+		event: zwlr_data_control_source_v1::Event,
+		_: &(),
+		_: &Connection,
+		_: &QueueHandle<Self>,
+	) {
+		match event {
+			zwlr_data_control_source_v1::Event::Send { mime_type, fd } => {
+				state.send(&mime_type, fd)
+			}
+			zwlr_data_control_source_v1::Event::Cancelled => state.cancelled = true,
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entries() -> Vec<(String, Vec<u8>)> {
+		vec![
+			("text/plain;charset=utf-8".to_string(), b"hello".to_vec()),
+			("image/png".to_string(), vec![0x89, b'P', b'N', b'G']),
+			("application/x-empty".to_string(), Vec::new()),
+		]
+	}
+
+	#[test]
+	fn round_trips_entries() {
+		let mut bytes = Vec::new();
+		write_entries(&mut bytes, &entries()).unwrap();
+		assert_eq!(read_entries(bytes.as_slice()).unwrap(), entries());
+
+		let mut bytes = Vec::new();
+		write_entries(&mut bytes, &[]).unwrap();
+		assert_eq!(read_entries(bytes.as_slice()).unwrap(), []);
+	}
+
+	#[test]
+	fn rejects_truncated_entries() {
+		let mut bytes = Vec::new();
+		write_entries(&mut bytes, &entries()).unwrap();
+
+		for length in 0..bytes.len() {
+			let error = read_entries(&bytes[..length]).unwrap_err();
+			assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "{length} bytes");
+		}
+	}
+
+	#[test]
+	fn rejects_invalid_mime_types() {
+		let mut bytes = Vec::new();
+		write_entries(&mut bytes, &entries()).unwrap();
+		// The first byte of the first mime type.
+		bytes[8] = 0xff;
+
+		let error = read_entries(bytes.as_slice()).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+	}
+}
@@ -16,6 +16,20 @@ pub struct AtomManager {
 	pub targets: Atom,
 	/// "INCR" (for incremental clipboard transfers)
 	pub incr: Atom,
+	/// "TIMESTAMP"
+	pub timestamp: Atom,
+	/// "MULTIPLE"
+	pub multiple: Atom,
+	/// "ATOM_PAIR"
+	pub atom_pair: Atom,
+	/// "CLIPBOARD_MANAGER"
+	pub clipboard_manager: Atom,
+	/// "SAVE_TARGETS"
+	pub save_targets: Atom,
+	/// "TEXT"
+	pub text: Atom,
+	/// "UTF8_STRING"
+	pub utf8_string: Atom,
 }
 
 impl AtomManager {
@@ -25,6 +39,13 @@ impl AtomManager {
 			clipboard: Self::get_atom(conn, b"CLIPBOARD")?,
 			targets: Self::get_atom(conn, b"TARGETS")?,
 			incr: Self::get_atom(conn, b"INCR")?,
+			timestamp: Self::get_atom(conn, b"TIMESTAMP")?,
+			multiple: Self::get_atom(conn, b"MULTIPLE")?,
+			atom_pair: Self::get_atom(conn, b"ATOM_PAIR")?,
+			clipboard_manager: Self::get_atom(conn, b"CLIPBOARD_MANAGER")?,
+			save_targets: Self::get_atom(conn, b"SAVE_TARGETS")?,
+			text: Self::get_atom(conn, b"TEXT")?,
+			utf8_string: Self::get_atom(conn, b"UTF8_STRING")?,
 		})
 	}
 
@@ -0,0 +1,71 @@
+use std::{
+	sync::mpsc,
+	thread::{self, ThreadId},
+};
+
+use crate::{
+	ClipboardBackend, ClipboardError,
+	internal::InternalDataAccess,
+	platform::x11::{CommandSender, ThreadCommand, paste_data_access::SelectionIdentity},
+};
+
+/// Reads through the clipboard thread, which first checks that the owner and its `TIMESTAMP`
+/// are still the ones captured with the mime types. That check is an extra round trip for every
+/// read.
+#[derive(Clone)]
+pub struct X11DataAccessHandle {
+	mime_types: Vec<String>,
+	identity: SelectionIdentity,
+	sender: CommandSender,
+	thread: ThreadId,
+}
+
+impl X11DataAccessHandle {
+	/// # Warning
+	///
+	/// Only use on the clipboard thread.
+	pub fn new(
+		mime_types: Vec<String>,
+		identity: SelectionIdentity,
+		sender: CommandSender,
+	) -> Self {
+		Self {
+			mime_types,
+			identity,
+			sender,
+			thread: thread::current().id(),
+		}
+	}
+}
+
+impl InternalDataAccess for X11DataAccessHandle {
+	fn backend(&self) -> ClipboardBackend {
+		ClipboardBackend::X11
+	}
+
+	fn mime_types(&self) -> &[String] {
+		&self.mime_types
+	}
+
+	fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError> {
+		// The clipboard thread would wait for itself.
+		if thread::current().id() == self.thread {
+			return Err(ClipboardError::InUse);
+		}
+
+		let (reply, receiver) = mpsc::channel();
+		let command = ThreadCommand::GetHandleData {
+			mime_type: mime_type.to_string(),
+			identity: self.identity,
+			reply,
+		};
+
+		if self.sender.send(command).is_err() {
+			return Err(ClipboardError::ClipboardDataUnavailable);
+		}
+
+		receiver
+			.recv()
+			.unwrap_or(Err(ClipboardError::ClipboardDataUnavailable))
+	}
+}
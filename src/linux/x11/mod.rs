@@ -1,14 +1,26 @@
 pub mod atoms;
+pub mod data_access_handle;
 pub mod paste_data_access;
+#[cfg(feature = "unstable_write")]
+pub mod selection_owner;
 
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
 use std::rc::Rc;
-use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
+#[cfg(feature = "unstable_write")]
+use std::time::Instant;
 
-pub use crate::platform::x11::paste_data_access::ConnectionHandler;
-use crate::{ClipboardEvent, ClipboardEventSource, ClipboardHandler, DataAccess};
+#[cfg(feature = "unstable_write")]
+use crate::WriteToClipboard;
+pub use crate::platform::x11::data_access_handle::X11DataAccessHandle;
+pub use crate::platform::x11::paste_data_access::{ConnectionHandler, SelectionIdentity};
+use crate::{ClipboardError, ClipboardEvent, ClipboardEventSource, ClipboardHandler, DataAccess};
 
 use raw_window_handle::HasDisplayHandle;
+use x11rb::connection::Connection as _;
 #[allow(unused_imports)]
 use x11rb::protocol::xproto::{
 	Atom, ConnectionExt, CreateWindowAux, EventMask, GetPropertyReply, Window, WindowClass,
@@ -17,58 +29,171 @@ use x11rb::protocol::xproto::{
 
 use crate::InternalClipboard;
 
-enum ThreadCommand {
+pub enum ThreadCommand {
 	GetData,
+	GetHandleData {
+		mime_type: String,
+		identity: SelectionIdentity,
+		reply: Sender<Result<Vec<u8>, ClipboardError>>,
+	},
+	/// The selection is given up after `timeout`, if it is set.
+	#[cfg(feature = "unstable_write")]
+	Write {
+		data: Box<dyn WriteToClipboard>,
+		timeout: Option<Duration>,
+	},
+	#[cfg(feature = "unstable_write")]
+	Persist,
 	Exit,
 }
 
-pub struct X11Clipboard {
+/// Sends commands to the clipboard thread, and wakes it up while it waits for X11 events.
+#[derive(Clone)]
+pub struct CommandSender {
 	sender: Sender<ThreadCommand>,
+	/// An eventfd, which the thread waits on together with the X11 connection.
+	wake: Arc<OwnedFd>,
+}
+
+impl CommandSender {
+	fn new() -> (Self, Receiver<ThreadCommand>, Arc<OwnedFd>) {
+		let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+		assert!(
+			fd >= 0,
+			"Failed to create an eventfd for the X11 clipboard thread."
+		);
+		let wake = Arc::new(unsafe { OwnedFd::from_raw_fd(fd) });
+
+		let (sender, receiver) = mpsc::channel();
+		let command_sender = Self {
+			sender,
+			wake: wake.clone(),
+		};
+		(command_sender, receiver, wake)
+	}
+
+	pub fn send(&self, command: ThreadCommand) -> Result<(), SendError<ThreadCommand>> {
+		self.sender.send(command)?;
+		let _ = unsafe { libc::eventfd_write(self.wake.as_raw_fd(), 1) };
+		Ok(())
+	}
+}
+
+pub struct X11Clipboard {
+	sender: CommandSender,
 	join_handle: Option<JoinHandle<()>>,
 }
 
 impl InternalClipboard for X11Clipboard {
 	fn new<T: ClipboardHandler>(_display_handle: &dyn HasDisplayHandle, mut handler: T) -> Self {
-		let (sender, receiver) = mpsc::channel();
+		let (sender, receiver, wake) = CommandSender::new();
+		let thread_sender = sender.clone();
 		let join_handle = Some(thread::spawn(move || {
 			let connection = Rc::new(ConnectionHandler::new());
 			let mut event_conut = 0;
+			#[cfg(feature = "unstable_write")]
+			let mut expires_at: Option<Instant> = None;
 
-			for command in receiver {
-				match command {
-					ThreadCommand::GetData => {
-						let source = ClipboardEventSource { value: event_conut };
-						event_conut += 1;
-
-						handler.handle_event(ClipboardEvent::StartedPasteHandling { source });
-
-						let mime_types = match connection.mime_types() {
-							Ok(mime_types) => mime_types,
-							Err(error) => {
-								handler.handle_event(ClipboardEvent::FailedPasteHandling {
-									source,
-									error,
-								});
-								continue;
+			'thread: loop {
+				for command in receiver.try_iter() {
+					match command {
+						ThreadCommand::GetData => {
+							let source = ClipboardEventSource { value: event_conut };
+							event_conut += 1;
+
+							handler.handle_event(ClipboardEvent::StartedPasteHandling { source });
+
+							match data_access(&connection, &thread_sender) {
+								Ok(data_access) => {
+									handler.handle_event(ClipboardEvent::PasteResult {
+										source,
+										data: &data_access,
+									});
+								}
+								Err(error) => {
+									handler.handle_event(ClipboardEvent::FailedPasteHandling {
+										source,
+										error,
+									});
+								}
+							}
+						}
+						ThreadCommand::GetHandleData {
+							mime_type,
+							identity,
+							reply,
+						} => {
+							let result = if connection.selection_identity() == identity {
+								connection.get_raw_data(&mime_type)
+							} else {
+								Err(ClipboardError::Stale)
+							};
+							let _ = reply.send(result);
+						}
+						#[cfg(feature = "unstable_write")]
+						ThreadCommand::Write { data, timeout } => {
+							expires_at = None;
+							match connection.write(data) {
+								Ok(()) => {
+									expires_at = timeout.map(|timeout| Instant::now() + timeout)
+								}
+								Err(error) => {
+									log::error!("Failed to write to the X11 clipboard: {error:?}")
+								}
 							}
-						};
+						}
+						#[cfg(feature = "unstable_write")]
+						ThreadCommand::Persist => persist(&connection),
+						ThreadCommand::Exit => break 'thread,
+					}
+				}
+
+				#[cfg(feature = "unstable_write")]
+				if expires_at.is_some_and(|expires_at| Instant::now() >= expires_at) {
+					expires_at = None;
+					match connection.release_selection() {
+						Ok(true) => {
+							let source = ClipboardEventSource { value: event_conut };
+							event_conut += 1;
+							handler.handle_event(ClipboardEvent::Expired { source });
+						}
+						Ok(false) => {}
+						Err(error) => {
+							log::error!("Failed to clear the X11 clipboard: {error:?}")
+						}
+					}
+				}
+
+				// Reporting a change queues events of its own, which are handled before waiting.
+				loop {
+					connection.process_events();
+					if !connection.take_selection_changed() {
+						break;
+					}
 
-						let data_access = super::DataAccess::X11 {
-							conn: connection.clone(),
-							mime_types,
-						};
-						let data_access = DataAccess {
-							internal: data_access,
-						};
+					let source = ClipboardEventSource { value: event_conut };
+					event_conut += 1;
 
-						handler.handle_event(ClipboardEvent::PasteResult {
+					// The clipboard can be empty after a change, which is nothing to report.
+					if let Ok(data_access) = data_access(&connection, &thread_sender) {
+						handler.handle_event(ClipboardEvent::ClipboardChanged {
 							source,
 							data: &data_access,
 						});
 					}
-					ThreadCommand::Exit => break,
 				}
+
+				#[cfg(feature = "unstable_write")]
+				let timeout = expires_at.map(|expires_at| expires_at - Instant::now());
+				#[cfg(not(feature = "unstable_write"))]
+				let timeout = None;
+
+				wait(&connection, Some(&wake), timeout);
 			}
+
+			// Hand our content to the clipboard manager, before it is lost with our window.
+			#[cfg(feature = "unstable_write")]
+			persist(&connection);
 		}));
 
 		X11Clipboard {
@@ -82,8 +207,81 @@ impl InternalClipboard for X11Clipboard {
 	}
 
 	#[cfg(feature = "unstable_write")]
-	fn write(&self, _data: ClipboardData) {
-		unimplemented!("Clipboard write not implemented yet.");
+	fn write<T: WriteToClipboard>(&self, data: T) {
+		let _ = self.sender.send(ThreadCommand::Write {
+			data: Box::new(data),
+			timeout: None,
+		});
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_expiring<T: WriteToClipboard>(&self, data: T, timeout: Duration) {
+		let _ = self.sender.send(ThreadCommand::Write {
+			data: Box::new(data),
+			timeout: Some(timeout),
+		});
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn persist(&self) {
+		let _ = self.sender.send(ThreadCommand::Persist);
+	}
+
+	/// The clipboard manager already keeps the content after the program exits.
+	#[cfg(feature = "unstable_write")]
+	fn write_persistent<T: WriteToClipboard>(&self, data: T) -> Result<(), ClipboardError> {
+		self.write(data);
+		self.persist();
+		Ok(())
+	}
+}
+
+fn data_access(
+	connection: &Rc<ConnectionHandler>,
+	sender: &CommandSender,
+) -> Result<DataAccess, ClipboardError> {
+	// Taken first, so a change while the types are fetched makes handles stale.
+	let identity = connection.selection_identity();
+	let mime_types = connection.mime_types()?;
+	let data_access = super::DataAccess::X11 {
+		conn: connection.clone(),
+		mime_types,
+		identity,
+		sender: sender.clone(),
+	};
+
+	Ok(DataAccess::new(data_access))
+}
+
+/// Blocks until X11 events arrive, commands arrive if `wake` is set, or `timeout` passes.
+///
+/// Events already read from the connection must be handled before, as they do not wake this up.
+fn wait(connection: &ConnectionHandler, wake: Option<&OwnedFd>, timeout: Option<Duration>) {
+	let _ = connection.conn.flush();
+
+	// poll ignores negative fds.
+	let wake_fd = wake.map_or(-1, |wake| wake.as_raw_fd());
+	let mut fds = [connection.conn.stream().as_raw_fd(), wake_fd].map(|fd| libc::pollfd {
+		fd,
+		events: libc::POLLIN,
+		revents: 0,
+	});
+	// Rounded up, so the thread does not spin during the last millisecond.
+	let timeout = timeout.map_or(-1, |timeout| {
+		timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32
+	});
+	unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) };
+
+	if let Some(wake) = wake {
+		let mut count = 0;
+		let _ = unsafe { libc::eventfd_read(wake.as_raw_fd(), &mut count) };
+	}
+}
+
+#[cfg(feature = "unstable_write")]
+fn persist(connection: &ConnectionHandler) {
+	if let Err(error) = connection.save_targets() {
+		log::warn!("Failed to hand the X11 clipboard to the clipboard manager: {error:?}");
 	}
 }
 
@@ -1,27 +1,48 @@
-use std::time::{Duration, Instant};
+#[cfg(feature = "unstable_write")]
+use std::cell::RefCell;
+use std::{
+	cell::Cell,
+	time::{Duration, Instant},
+};
 
 use x11rb::{
 	CURRENT_TIME,
 	connection::Connection,
 	protocol::{
 		Event,
+		xfixes::{ConnectionExt as _, SelectionEventMask},
 		xproto::{
-			self, Atom, ConnectionExt, CreateWindowAux, Property, Window, WindowClass,
-			create_window,
+			self, Atom, AtomEnum, ConnectionExt, CreateWindowAux, Property, Timestamp, Window,
+			WindowClass, create_window,
 		},
 	},
 	rust_connection::RustConnection,
 };
 
+#[cfg(feature = "unstable_write")]
+use crate::platform::x11::selection_owner::{IncrTransfers, OwnedSelection};
 use crate::{ClipboardError, platform::x11::atoms::AtomManager};
 
-const TIMEOUT_LIMIT: Duration = Duration::from_secs(2);
+pub(super) const TIMEOUT_LIMIT: Duration = Duration::from_secs(2);
+
+/// Identifies the current clipboard content by its owner and the time it was acquired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionIdentity {
+	owner: Window,
+	timestamp: Option<Timestamp>,
+}
 
 pub struct ConnectionHandler {
-	conn: RustConnection,
-	window: Window,
-	atoms: AtomManager,
-	property: Atom,
+	pub(super) conn: RustConnection,
+	pub(super) window: Window,
+	pub(super) atoms: AtomManager,
+	pub(super) property: Atom,
+	selection_changed: Cell<bool>,
+	#[cfg(feature = "unstable_write")]
+	pub(super) owned_selection: RefCell<Option<OwnedSelection>>,
+	/// Conversions, which are too large for a single property and are sent in chunks.
+	#[cfg(feature = "unstable_write")]
+	pub(super) incr_transfers: RefCell<IncrTransfers>,
 }
 
 impl ConnectionHandler {
@@ -46,12 +67,66 @@ impl ConnectionHandler {
 			&CreateWindowAux::new().event_mask(xproto::EventMask::PROPERTY_CHANGE),
 		);
 
+		let atoms = AtomManager::new(&conn).unwrap();
+
+		// XFixes reports selection owner changes, without it changes are not noticed.
+		let watching = conn
+			.xfixes_query_version(5, 0)
+			.ok()
+			.and_then(|cookie| cookie.reply().ok())
+			.and_then(|_| {
+				conn.xfixes_select_selection_input(
+					window,
+					atoms.clipboard,
+					SelectionEventMask::SET_SELECTION_OWNER
+						| SelectionEventMask::SELECTION_WINDOW_DESTROY
+						| SelectionEventMask::SELECTION_CLIENT_CLOSE,
+				)
+				.ok()
+			})
+			.is_some();
+		if !watching {
+			log::warn!("XFixes is not available, clipboard changes will not be reported.");
+		}
+
 		Self {
 			window,
-			atoms: AtomManager::new(&conn).unwrap(),
+			atoms,
 			property: AtomManager::get_atom(&conn, b"GENGINE CLIPBOARD RECEIVER").unwrap(),
+			selection_changed: Cell::new(false),
 			conn,
+			#[cfg(feature = "unstable_write")]
+			owned_selection: RefCell::new(None),
+			#[cfg(feature = "unstable_write")]
+			incr_transfers: RefCell::default(),
+		}
+	}
+
+	/// Handles all events that are already queued on the connection.
+	pub fn process_events(&self) {
+		while let Ok(Some(event)) = self.conn.poll_for_event() {
+			self.handle_other_event(event);
+		}
+	}
+
+	/// Whether the clipboard owner changed since the last call.
+	pub fn take_selection_changed(&self) -> bool {
+		self.selection_changed.replace(false)
+	}
+
+	/// Handles an event, which is not part of an ongoing transfer.
+	pub(super) fn handle_other_event(&self, event: Event) {
+		if let Event::XfixesSelectionNotify(event) = &event
+			&& event.selection == self.atoms.clipboard
+		{
+			self.selection_changed.set(true);
 		}
+
+		#[cfg(feature = "unstable_write")]
+		self.handle_owner_event(event);
+
+		#[cfg(not(feature = "unstable_write"))]
+		let _ = event;
 	}
 
 	fn get_selection(&self, mut target: Atom) -> Result<Vec<u8>, ClipboardError> {
@@ -72,6 +147,8 @@ impl ConnectionHandler {
 		// When requesting targets, we get a list of atoms
 		if target == self.atoms.targets {
 			target = self.atoms.atom
+		} else if target == self.atoms.timestamp {
+			target = AtomEnum::INTEGER.into()
 		}
 
 		let mut last_event = Instant::now();
@@ -139,7 +216,9 @@ impl ConnectionHandler {
 					data.extend_from_slice(&data_reply.value);
 					break;
 				}
-				Event::PropertyNotify(event) if incr => {
+				Event::PropertyNotify(event)
+					if incr && event.window == self.window && event.atom == self.property =>
+				{
 					if event.state != Property::NEW_VALUE {
 						continue;
 					}
@@ -172,7 +251,7 @@ impl ConnectionHandler {
 					data.extend_from_slice(&reply.value);
 					last_event = Instant::now();
 				}
-				_ => {}
+				event => self.handle_other_event(event),
 			}
 		}
 
@@ -195,6 +274,25 @@ impl ConnectionHandler {
 		Ok(names)
 	}
 
+	/// Owners which do not support the TIMESTAMP target are only identified by their window.
+	pub fn selection_identity(&self) -> SelectionIdentity {
+		let owner = self
+			.conn
+			.get_selection_owner(self.atoms.clipboard)
+			.ok()
+			.and_then(|cookie| cookie.reply().ok())
+			.map(|reply| reply.owner)
+			.unwrap_or(x11rb::NONE);
+
+		let timestamp = self
+			.get_selection(self.atoms.timestamp)
+			.ok()
+			.and_then(|bytes| bytes.first_chunk::<4>().copied())
+			.map(u32::from_ne_bytes);
+
+		SelectionIdentity { owner, timestamp }
+	}
+
 	pub fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError> {
 		let target = AtomManager::get_atom(&self.conn, mime_type.as_bytes()).unwrap();
 		self.get_selection(target)
@@ -0,0 +1,586 @@
+use std::time::Instant;
+
+use x11rb::{
+	NONE,
+	connection::{Connection, RequestConnection},
+	protocol::{
+		Event,
+		xproto::{
+			Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask, PropMode,
+			Property, SELECTION_NOTIFY_EVENT, SelectionNotifyEvent, SelectionRequestEvent,
+			Timestamp, Window,
+		},
+	},
+	wrapper::ConnectionExt as _,
+};
+
+use crate::{
+	ClipboardError, WriteToClipboard,
+	classify::SELECTION_TARGETS,
+	platform::x11::{
+		atoms::AtomManager,
+		paste_data_access::{ConnectionHandler, TIMEOUT_LIMIT},
+		wait,
+	},
+};
+
+/// Largest chunk of an INCR transfer. Larger conversions are sent incrementally as well.
+const INCR_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Data offered to other clients while this connection owns the clipboard selection.
+pub struct OwnedSelection {
+	data: Box<dyn WriteToClipboard>,
+	targets: Vec<(Atom, String)>,
+	timestamp: Timestamp,
+}
+
+/// A conversion sent with the INCR protocol, one chunk each time the requestor deleted the last.
+struct IncrTransfer {
+	requestor: Window,
+	property: Atom,
+	property_type: Atom,
+	bytes: Vec<u8>,
+	/// Bytes sent so far.
+	offset: usize,
+	last_activity: Instant,
+}
+
+/// INCR transfers in progress, at most one per requestor and property.
+#[derive(Default)]
+pub struct IncrTransfers {
+	transfers: Vec<IncrTransfer>,
+}
+
+impl IncrTransfers {
+	/// Adds a transfer, replacing one to the same property. Transfers to requestors, which
+	/// vanished and never delete the property again, are dropped.
+	fn start(&mut self, requestor: Window, property: Atom, property_type: Atom, bytes: Vec<u8>) {
+		self.transfers.retain(|transfer| {
+			transfer.last_activity.elapsed() < TIMEOUT_LIMIT
+				&& (transfer.requestor, transfer.property) != (requestor, property)
+		});
+		self.transfers.push(IncrTransfer {
+			requestor,
+			property,
+			property_type,
+			bytes,
+			offset: 0,
+			last_activity: Instant::now(),
+		});
+	}
+
+	/// Takes the type and the next chunk of the transfer to `property` of `requestor`.
+	///
+	/// The last chunk is empty, the transfer is removed with it.
+	fn next_chunk(
+		&mut self,
+		requestor: Window,
+		property: Atom,
+		chunk_size: usize,
+	) -> Option<(Atom, Vec<u8>)> {
+		let index = self.position(requestor, property)?;
+		let transfer = &mut self.transfers[index];
+		let end = (transfer.offset + chunk_size).min(transfer.bytes.len());
+		let chunk = transfer.bytes[transfer.offset..end].to_vec();
+		let property_type = transfer.property_type;
+		transfer.offset = end;
+		transfer.last_activity = Instant::now();
+
+		if chunk.is_empty() {
+			self.transfers.remove(index);
+		}
+		Some((property_type, chunk))
+	}
+
+	fn cancel(&mut self, requestor: Window, property: Atom) {
+		if let Some(index) = self.position(requestor, property) {
+			self.transfers.remove(index);
+		}
+	}
+
+	fn has_requestor(&self, requestor: Window) -> bool {
+		self.transfers
+			.iter()
+			.any(|transfer| transfer.requestor == requestor)
+	}
+
+	fn position(&self, requestor: Window, property: Atom) -> Option<usize> {
+		self.transfers
+			.iter()
+			.position(|transfer| transfer.requestor == requestor && transfer.property == property)
+	}
+}
+
+impl ConnectionHandler {
+	pub fn write(&self, data: Box<dyn WriteToClipboard>) -> Result<(), ClipboardError> {
+		let timestamp = self.server_time()?;
+
+		let mut targets = Vec::new();
+		// These are answered by the owner itself.
+		let conversions = data
+			.viable_conversions()
+			.into_iter()
+			.filter(|mime_type| !SELECTION_TARGETS.contains(&mime_type.as_str()));
+		for mime_type in conversions {
+			match AtomManager::get_atom(&self.conn, mime_type.as_bytes()) {
+				Ok(atom) => targets.push((atom, mime_type)),
+				Err(_) => log::warn!("Failed to intern atom for {mime_type}, not offering it."),
+			}
+		}
+
+		*self.owned_selection.borrow_mut() = Some(OwnedSelection {
+			data,
+			targets,
+			timestamp,
+		});
+
+		let owner = self
+			.conn
+			.set_selection_owner(self.window, self.atoms.clipboard, timestamp)
+			.and_then(|_| self.conn.get_selection_owner(self.atoms.clipboard))
+			.map_err(|_| ClipboardError::SetFailed)?
+			.reply()
+			.map_err(|_| ClipboardError::SetFailed)?
+			.owner;
+
+		if owner != self.window {
+			self.owned_selection.borrow_mut().take();
+			return Err(ClipboardError::SetFailed);
+		}
+
+		Ok(())
+	}
+
+	/// Gives up the clipboard selection, if our window still owns it.
+	///
+	/// Returns whether the selection was given up.
+	pub fn release_selection(&self) -> Result<bool, ClipboardError> {
+		let timestamp = match self.owned_selection.borrow().as_ref() {
+			Some(owned) => owned.timestamp,
+			None => return Ok(false),
+		};
+
+		let owner = self
+			.conn
+			.get_selection_owner(self.atoms.clipboard)
+			.map_err(|_| ClipboardError::SetFailed)?
+			.reply()
+			.map_err(|_| ClipboardError::SetFailed)?
+			.owner;
+		if owner != self.window {
+			self.owned_selection.borrow_mut().take();
+			return Ok(false);
+		}
+
+		// With our timestamp the server ignores this, if another client took the selection since.
+		self.conn
+			.set_selection_owner(NONE, self.atoms.clipboard, timestamp)
+			.map_err(|_| ClipboardError::SetFailed)?;
+		self.conn.flush().map_err(|_| ClipboardError::SetFailed)?;
+		self.owned_selection.borrow_mut().take();
+
+		Ok(true)
+	}
+
+	/// Asks the clipboard manager to copy our content, so it outlives this connection.
+	///
+	/// This is the `SAVE_TARGETS` handshake from the ICCCM. Nothing happens, if we do not own
+	/// the clipboard or no clipboard manager is running.
+	pub fn save_targets(&self) -> Result<(), ClipboardError> {
+		let (targets, timestamp) = match self.owned_selection.borrow().as_ref() {
+			Some(owned) => (
+				owned
+					.targets
+					.iter()
+					.map(|(atom, _)| *atom)
+					.collect::<Vec<_>>(),
+				owned.timestamp,
+			),
+			None => return Ok(()),
+		};
+
+		let manager = self
+			.conn
+			.get_selection_owner(self.atoms.clipboard_manager)
+			.map_err(|_| ClipboardError::SetFailed)?
+			.reply()
+			.map_err(|_| ClipboardError::SetFailed)?
+			.owner;
+		if manager == NONE {
+			log::info!("No clipboard manager is running, the clipboard is lost on exit.");
+			return Ok(());
+		}
+
+		self.conn
+			.change_property32(
+				PropMode::REPLACE,
+				self.window,
+				self.property,
+				AtomEnum::ATOM,
+				&targets,
+			)
+			.map_err(|_| ClipboardError::SetFailed)?;
+		self.conn
+			.convert_selection(
+				self.window,
+				self.atoms.clipboard_manager,
+				self.atoms.save_targets,
+				self.property,
+				timestamp,
+			)
+			.map_err(|_| ClipboardError::SetFailed)?;
+		self.conn.flush().map_err(|_| ClipboardError::SetFailed)?;
+
+		// The manager requests our data in the meantime, so the timeout restarts with every request.
+		let mut last_event = Instant::now();
+		loop {
+			let Some(remaining) = TIMEOUT_LIMIT.checked_sub(last_event.elapsed()) else {
+				return Err(ClipboardError::Timeout);
+			};
+
+			match self.conn.poll_for_event() {
+				Ok(Some(Event::SelectionNotify(event)))
+					if event.selection == self.atoms.clipboard_manager =>
+				{
+					return match event.property {
+						NONE => Err(ClipboardError::SetFailed),
+						_ => Ok(()),
+					};
+				}
+				Ok(Some(event)) => {
+					if matches!(event, Event::SelectionRequest(_) | Event::PropertyNotify(_)) {
+						last_event = Instant::now();
+					}
+					self.handle_other_event(event);
+				}
+				// Commands wait until the handshake is over.
+				Ok(None) => wait(self, None, Some(remaining)),
+				Err(_) => return Err(ClipboardError::SetFailed),
+			}
+		}
+	}
+
+	pub(super) fn handle_owner_event(&self, event: Event) {
+		match event {
+			Event::SelectionRequest(event) => self.handle_selection_request(event),
+			Event::SelectionClear(event) if event.selection == self.atoms.clipboard => {
+				self.owned_selection.borrow_mut().take();
+			}
+			Event::PropertyNotify(event) if event.state == Property::DELETE => {
+				self.continue_incr(event.window, event.atom);
+			}
+			_ => {}
+		}
+	}
+
+	fn incr_chunk_size(&self) -> usize {
+		INCR_CHUNK_SIZE.min(self.conn.maximum_request_bytes() - 32)
+	}
+
+	/// Announces an INCR transfer of `bytes`, whose chunks follow as the requestor deletes them.
+	fn start_incr(
+		&self,
+		requestor: Window,
+		property: Atom,
+		property_type: Atom,
+		bytes: Vec<u8>,
+	) -> bool {
+		let started = self
+			.conn
+			.change_window_attributes(
+				requestor,
+				&ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+			)
+			.and_then(|_| {
+				self.conn.change_property32(
+					PropMode::REPLACE,
+					requestor,
+					property,
+					self.atoms.incr,
+					&[bytes.len().min(u32::MAX as usize) as u32],
+				)
+			})
+			.is_ok();
+
+		if started {
+			self.incr_transfers
+				.borrow_mut()
+				.start(requestor, property, property_type, bytes);
+		}
+
+		started
+	}
+
+	/// Sends the next chunk of the transfer to `requestor`, once it deleted `property`.
+	///
+	/// A transfer ends with a zero length chunk. The requestor's events are only unselected once
+	/// none of its transfers is left, as a `MULTIPLE` request can run several at once.
+	fn continue_incr(&self, requestor: Window, property: Atom) {
+		let mut transfers = self.incr_transfers.borrow_mut();
+		let chunk_size = self.incr_chunk_size();
+		let Some((property_type, chunk)) = transfers.next_chunk(requestor, property, chunk_size)
+		else {
+			return;
+		};
+
+		let sent = self
+			.conn
+			.change_property8(
+				PropMode::REPLACE,
+				requestor,
+				property,
+				property_type,
+				&chunk,
+			)
+			.is_ok();
+		if !sent {
+			transfers.cancel(requestor, property);
+		}
+
+		if !transfers.has_requestor(requestor) {
+			let _ = self.conn.change_window_attributes(
+				requestor,
+				&ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT),
+			);
+		}
+		let _ = self.conn.flush();
+	}
+
+	/// Gets the current server time by waiting for a zero length property change.
+	///
+	/// ICCCM forbids using CurrentTime when acquiring a selection.
+	fn server_time(&self) -> Result<Timestamp, ClipboardError> {
+		self.conn
+			.change_property8(
+				PropMode::APPEND,
+				self.window,
+				self.property,
+				AtomEnum::STRING,
+				&[],
+			)
+			.map_err(|_| ClipboardError::SetFailed)?;
+		self.conn.flush().map_err(|_| ClipboardError::SetFailed)?;
+
+		let start = Instant::now();
+		loop {
+			if Instant::now() - start > TIMEOUT_LIMIT {
+				return Err(ClipboardError::Timeout);
+			}
+
+			match self.conn.wait_for_event() {
+				Ok(Event::PropertyNotify(event))
+					if event.window == self.window && event.atom == self.property =>
+				{
+					return Ok(event.time);
+				}
+				Ok(event) => self.handle_other_event(event),
+				Err(_) => return Err(ClipboardError::SetFailed),
+			}
+		}
+	}
+
+	fn handle_selection_request(&self, event: SelectionRequestEvent) {
+		// Obsolete clients send None as the property, ICCCM says to use the target instead.
+		let property = if event.property == NONE {
+			event.target
+		} else {
+			event.property
+		};
+
+		let converted = self.convert(event.requestor, event.selection, event.target, property);
+		let notify = SelectionNotifyEvent {
+			response_type: SELECTION_NOTIFY_EVENT,
+			sequence: 0,
+			time: event.time,
+			requestor: event.requestor,
+			selection: event.selection,
+			target: event.target,
+			property: if converted { property } else { NONE },
+		};
+
+		let _ = self
+			.conn
+			.send_event(false, event.requestor, EventMask::NO_EVENT, notify);
+		let _ = self.conn.flush();
+	}
+
+	fn convert(&self, requestor: Window, selection: Atom, target: Atom, property: Atom) -> bool {
+		let owned_selection = self.owned_selection.borrow();
+		let owned = match owned_selection.as_ref() {
+			Some(owned) if selection == self.atoms.clipboard => owned,
+			_ => return false,
+		};
+
+		if target == self.atoms.multiple {
+			return self.convert_multiple(requestor, selection, property);
+		}
+
+		if target == self.atoms.targets {
+			let mut atoms = vec![
+				self.atoms.targets,
+				self.atoms.timestamp,
+				self.atoms.multiple,
+			];
+			atoms.extend(owned.targets.iter().map(|(atom, _)| *atom));
+			return self
+				.conn
+				.change_property32(
+					PropMode::REPLACE,
+					requestor,
+					property,
+					AtomEnum::ATOM,
+					&atoms,
+				)
+				.is_ok();
+		}
+
+		if target == self.atoms.timestamp {
+			return self
+				.conn
+				.change_property32(
+					PropMode::REPLACE,
+					requestor,
+					property,
+					AtomEnum::INTEGER,
+					&[owned.timestamp],
+				)
+				.is_ok();
+		}
+
+		let Some((_, mime_type)) = owned.targets.iter().find(|(atom, _)| *atom == target) else {
+			return false;
+		};
+
+		let Some(bytes) = owned.data.convert_to(mime_type) else {
+			return false;
+		};
+
+		// TEXT asks the owner to pick an encoding, so the reply names the one it picked.
+		let property_type = if target == self.atoms.text {
+			self.atoms.utf8_string
+		} else {
+			target
+		};
+
+		if bytes.len() > self.incr_chunk_size() {
+			return self.start_incr(requestor, property, property_type, bytes);
+		}
+
+		self.conn
+			.change_property8(
+				PropMode::REPLACE,
+				requestor,
+				property,
+				property_type,
+				&bytes,
+			)
+			.is_ok()
+	}
+
+	/// Converts all (target, property) pairs listed in `property`. Pairs that fail to convert
+	/// get their property replaced with None, as the ICCCM requires.
+	fn convert_multiple(&self, requestor: Window, selection: Atom, property: Atom) -> bool {
+		let Some(reply) = self
+			.conn
+			.get_property(false, requestor, property, AtomEnum::ANY, 0, u32::MAX)
+			.ok()
+			.and_then(|cookie| cookie.reply().ok())
+		else {
+			return false;
+		};
+
+		let Some(values) = reply.value32() else {
+			return false;
+		};
+
+		let mut pairs: Vec<u32> = values.collect();
+		convert_pairs(
+			&mut pairs,
+			self.atoms.multiple,
+			|target, target_property| self.convert(requestor, selection, target, target_property),
+		);
+
+		self.conn
+			.change_property32(PropMode::REPLACE, requestor, property, reply.type_, &pairs)
+			.is_ok()
+	}
+}
+
+/// Converts each (target, property) pair of a `MULTIPLE` request with `convert`, and replaces
+/// the property of failed pairs with None. Nested `MULTIPLE` requests are refused.
+fn convert_pairs(pairs: &mut [u32], multiple: Atom, mut convert: impl FnMut(Atom, Atom) -> bool) {
+	for pair in pairs.chunks_exact_mut(2) {
+		let (target, target_property) = (pair[0], pair[1]);
+		if target == multiple || !convert(target, target_property) {
+			pair[1] = NONE;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+
+	const MULTIPLE: Atom = 10;
+
+	#[test]
+	fn converts_pairs() {
+		let mut pairs = [1, 101, 2, 102, MULTIPLE, 103, 3, 104, 4];
+		let mut converted = Vec::new();
+		convert_pairs(&mut pairs, MULTIPLE, |target, property| {
+			converted.push((target, property));
+			target != 2
+		});
+
+		assert_eq!(converted, [(1, 101), (2, 102), (3, 104)]);
+		// The trailing atom without a property is left alone.
+		assert_eq!(pairs, [1, 101, 2, NONE, MULTIPLE, NONE, 3, 104, 4]);
+	}
+
+	#[test]
+	fn sends_transfers_in_chunks() {
+		let mut transfers = IncrTransfers::default();
+		transfers.start(1, 100, 7, vec![1, 2, 3, 4, 5]);
+
+		assert_eq!(transfers.next_chunk(1, 101, 2), None);
+		assert_eq!(transfers.next_chunk(1, 100, 2), Some((7, vec![1, 2])));
+		assert_eq!(transfers.next_chunk(1, 100, 2), Some((7, vec![3, 4])));
+		assert_eq!(transfers.next_chunk(1, 100, 2), Some((7, vec![5])));
+		assert!(transfers.has_requestor(1));
+		assert_eq!(transfers.next_chunk(1, 100, 2), Some((7, Vec::new())));
+		assert!(!transfers.has_requestor(1));
+		assert_eq!(transfers.next_chunk(1, 100, 2), None);
+	}
+
+	#[test]
+	fn keeps_other_transfers_of_a_requestor() {
+		let mut transfers = IncrTransfers::default();
+		transfers.start(1, 100, 7, vec![1]);
+		transfers.start(1, 101, 8, vec![2, 3]);
+		transfers.start(2, 100, 9, vec![4]);
+
+		assert_eq!(transfers.next_chunk(1, 100, 4), Some((7, vec![1])));
+		assert_eq!(transfers.next_chunk(1, 100, 4), Some((7, Vec::new())));
+		assert!(transfers.has_requestor(1));
+
+		transfers.cancel(1, 101);
+		assert!(!transfers.has_requestor(1));
+		assert!(transfers.has_requestor(2));
+	}
+
+	#[test]
+	fn replaces_and_drops_transfers() {
+		let mut transfers = IncrTransfers::default();
+		transfers.start(1, 100, 7, vec![1]);
+		transfers.start(1, 100, 8, vec![2]);
+		assert_eq!(transfers.next_chunk(1, 100, 4), Some((8, vec![2])));
+
+		transfers.transfers[0].last_activity =
+			Instant::now() - TIMEOUT_LIMIT - Duration::from_secs(1);
+		transfers.start(2, 100, 9, vec![3]);
+		assert!(!transfers.has_requestor(1));
+		assert!(transfers.has_requestor(2));
+	}
+}
@@ -0,0 +1,155 @@
+//! Names, under which the same format appears on different platforms.
+//!
+//! Every format has a canonical mime type, like `image/png`. Windows calls the same bytes `PNG`
+//! and X11 programs offer UTF-8 text as `UTF8_STRING`. Only names for identical bytes are
+//! aliases, formats which need a conversion, like `HTML Format` and `text/html`, are not.
+//!
+//! Applications can add their own aliases with [`register_alias`], for example for the names
+//! different versions of a program used for its private format.
+
+use std::sync::{LazyLock, RwLock};
+
+/// The kind of clipboard, which uses an alias.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasOrigin {
+	/// X11 atoms, which toolkits also offer on Wayland.
+	X11,
+	/// Windows clipboard format names.
+	Windows,
+	/// Types of the web clipboard API.
+	Web,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeAlias {
+	pub canonical: String,
+	pub alias: String,
+	pub origin: AliasOrigin,
+}
+
+const BUILTIN_ALIASES: [(&str, &str, AliasOrigin); 8] = [
+	("text/plain;charset=utf-8", "UTF8_STRING", AliasOrigin::X11),
+	("text/plain;charset=utf-8", "text/plain", AliasOrigin::Web),
+	(
+		"text/plain;charset=utf-16le",
+		"CF_UNICODETEXT",
+		AliasOrigin::Windows,
+	),
+	("image/png", "PNG", AliasOrigin::Windows),
+	("image/jpeg", "JFIF", AliasOrigin::Windows),
+	("image/gif", "GIF", AliasOrigin::Windows),
+	("text/rtf", "Rich Text Format", AliasOrigin::Windows),
+	("text/rtf", "application/rtf", AliasOrigin::X11),
+];
+
+static ALIASES: LazyLock<RwLock<Vec<MimeAlias>>> = LazyLock::new(|| {
+	let aliases = BUILTIN_ALIASES
+		.iter()
+		.map(|(canonical, alias, origin)| MimeAlias {
+			canonical: canonical.to_string(),
+			alias: alias.to_string(),
+			origin: *origin,
+		})
+		.collect();
+
+	RwLock::new(aliases)
+});
+
+fn with_aliases<R>(f: impl FnOnce(&[MimeAlias]) -> R) -> R {
+	// The table is never left half updated, so a poisoned lock is still fine to use.
+	let aliases = ALIASES.read().unwrap_or_else(|error| error.into_inner());
+	f(&aliases)
+}
+
+/// Makes `alias` another name of `canonical`.
+///
+/// Registering an alias twice has no effect. An alias can only belong to one canonical type,
+/// the first registration wins.
+pub fn register_alias(canonical: impl Into<String>, alias: impl Into<String>, origin: AliasOrigin) {
+	let alias = MimeAlias {
+		canonical: canonical.into(),
+		alias: alias.into(),
+		origin,
+	};
+
+	let mut aliases = ALIASES.write().unwrap_or_else(|error| error.into_inner());
+	if alias.canonical != alias.alias && !aliases.iter().any(|known| known.alias == alias.alias) {
+		aliases.push(alias);
+	}
+}
+
+/// Returns all registered aliases, the built-in ones first.
+pub fn all_aliases() -> Vec<MimeAlias> {
+	with_aliases(<[MimeAlias]>::to_vec)
+}
+
+/// Returns the canonical mime type of `raw_type`, which is `raw_type` itself if it is no alias.
+pub fn canonical_type(raw_type: &str) -> String {
+	with_aliases(|aliases| {
+		aliases
+			.iter()
+			.find(|alias| alias.alias == raw_type)
+			.map_or(raw_type, |alias| &alias.canonical)
+			.to_string()
+	})
+}
+
+/// Returns the canonical mime type of `mime_type` and all of its aliases.
+pub fn names_of(mime_type: &str) -> Vec<String> {
+	let canonical = canonical_type(mime_type);
+	with_aliases(|aliases| {
+		let aliases = aliases
+			.iter()
+			.filter(|alias| alias.canonical == canonical)
+			.map(|alias| alias.alias.clone());
+
+		std::iter::once(canonical.clone()).chain(aliases).collect()
+	})
+}
+
+/// Returns the name `origin` uses for the canonical `mime_type`, if it has an own one.
+pub fn alias_for(mime_type: &str, origin: AliasOrigin) -> Option<String> {
+	with_aliases(|aliases| {
+		aliases
+			.iter()
+			.find(|alias| alias.canonical == mime_type && alias.origin == origin)
+			.map(|alias| alias.alias.clone())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resolves_builtin_aliases() {
+		assert_eq!(canonical_type("PNG"), "image/png");
+		assert_eq!(canonical_type("image/png"), "image/png");
+		assert_eq!(canonical_type("x-unknown"), "x-unknown");
+		assert_eq!(
+			names_of("text/rtf"),
+			["text/rtf", "Rich Text Format", "application/rtf"]
+		);
+		assert_eq!(
+			alias_for("image/jpeg", AliasOrigin::Windows).as_deref(),
+			Some("JFIF")
+		);
+	}
+
+	#[test]
+	fn registers_application_aliases() {
+		register_alias(
+			"application/x-test-level",
+			"Test Level",
+			AliasOrigin::Windows,
+		);
+		// The first registration of an alias wins.
+		register_alias("application/x-other", "Test Level", AliasOrigin::Windows);
+
+		assert_eq!(canonical_type("Test Level"), "application/x-test-level");
+		assert_eq!(
+			names_of("Test Level"),
+			["application/x-test-level", "Test Level"]
+		);
+	}
+}
@@ -0,0 +1,111 @@
+use crate::{DataAccess, ReadFromClipboard};
+
+/// Reads one type of a tuple, see [`ReadFirst`].
+pub struct TypeReader<T> {
+	is_available: fn(&[&str]) -> bool,
+	read: fn(&DataAccess) -> Option<T>,
+}
+
+/// Tuples of [`ReadFromClipboard`] types, of which [`DataAccess::read_first`] reads one.
+///
+/// Implemented for tuples of two to six types.
+pub trait ReadFirst {
+	/// One of the `OneOf` enums, with a variant for each type of the tuple.
+	type Output;
+
+	/// Returns a reader per type, in the order of the tuple.
+	fn readers() -> Vec<TypeReader<Self::Output>>;
+}
+
+macro_rules! one_of {
+	($name:ident; $($variant:ident),+) => {
+		/// The type of a tuple, which was read by [`DataAccess::read_first`].
+		#[derive(Debug, Clone, PartialEq, Eq)]
+		pub enum $name<$($variant),+> {
+			$($variant($variant)),+
+		}
+
+		impl<$($variant: ReadFromClipboard),+> ReadFirst for ($($variant,)+) {
+			type Output = $name<$($variant),+>;
+
+			fn readers() -> Vec<TypeReader<Self::Output>> {
+				vec![$(TypeReader {
+					is_available: $variant::is_available,
+					read: |data| $variant::read(data).map($name::$variant),
+				}),+]
+			}
+		}
+	};
+}
+
+one_of!(OneOf2; A, B);
+one_of!(OneOf3; A, B, C);
+one_of!(OneOf4; A, B, C, D);
+one_of!(OneOf5; A, B, C, D, E);
+one_of!(OneOf6; A, B, C, D, E, F);
+
+pub(crate) fn read_first<T: ReadFirst>(data: &DataAccess) -> Option<T::Output> {
+	let available = data.raw_types();
+	T::readers()
+		.into_iter()
+		.filter(|reader| (reader.is_available)(&available))
+		.find_map(|reader| (reader.read)(data))
+}
+
+pub(crate) fn read_preferred<T: ReadFirst>(data: &DataAccess) -> Option<T::Output> {
+	let mut readers = T::readers();
+	for raw_type in data.raw_types() {
+		// Types are tried at the first raw type they accept, ties are broken by the tuple order.
+		let mut index = 0;
+		while index < readers.len() {
+			if !(readers[index].is_available)(&[raw_type]) {
+				index += 1;
+				continue;
+			}
+
+			let reader = readers.remove(index);
+			if let Some(value) = (reader.read)(data) {
+				return Some(value);
+			}
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ClipboardHtml, ClipboardSnapshot, ClipboardUrl};
+
+	fn snapshot() -> ClipboardSnapshot {
+		ClipboardSnapshot::new(vec![
+			("text/html".to_string(), b"<b>bold</b>".to_vec()),
+			("UTF8_STRING".to_string(), b"bold".to_vec()),
+		])
+	}
+
+	#[test]
+	fn reads_in_tuple_order() {
+		let data = snapshot().as_data_access();
+		assert_eq!(
+			data.read_first::<(String, ClipboardHtml)>(),
+			Some(OneOf2::A("bold".to_string()))
+		);
+		// Types, which fail to read, are skipped.
+		assert_eq!(
+			data.read_first::<(ClipboardUrl, ClipboardHtml, String)>()
+				.map(|read| matches!(read, OneOf3::B(_))),
+			Some(true)
+		);
+	}
+
+	#[test]
+	fn reads_in_source_order() {
+		let data = snapshot().as_data_access();
+		assert!(matches!(
+			data.read_preferred::<(String, ClipboardHtml)>(),
+			Some(OneOf2::B(_))
+		));
+	}
+}
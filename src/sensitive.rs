@@ -0,0 +1,122 @@
+//! Hints, which ask clipboard managers and histories not to store content, like passwords.
+
+use crate::DataAccess;
+#[cfg(feature = "unstable_write")]
+use crate::WriteToClipboard;
+
+/// KDE's hint, whose content is "secret" for sensitive content.
+const KDE_PASSWORD_HINT: &str = "x-kde-passwordManagerHint";
+/// Windows leaves out content with this format from clipboard monitoring. Its content is unused.
+const EXCLUDE_FROM_MONITOR: &str = "ExcludeClipboardContentFromMonitorProcessing";
+/// A DWORD, which keeps content out of the Windows clipboard history when it is zero.
+const CAN_INCLUDE_IN_HISTORY: &str = "CanIncludeInClipboardHistory";
+/// A DWORD, which keeps content out of the Windows cloud clipboard when it is zero.
+const CAN_UPLOAD_TO_CLOUD: &str = "CanUploadToCloudClipboard";
+
+#[cfg(all(feature = "unstable_write", target_os = "windows"))]
+const HINT_TYPES: [&str; 3] = [
+	EXCLUDE_FROM_MONITOR,
+	CAN_INCLUDE_IN_HISTORY,
+	CAN_UPLOAD_TO_CLOUD,
+];
+
+#[cfg(all(feature = "unstable_write", not(target_os = "windows")))]
+const HINT_TYPES: [&str; 1] = [KDE_PASSWORD_HINT];
+
+/// Checks the hints of all platforms, as content may come from a snapshot of another one.
+///
+/// Hints, whose content cannot be fetched, count as sensitive.
+pub(crate) fn is_sensitive(data: &DataAccess) -> bool {
+	let is_zero_dword = |bytes: Vec<u8>| bytes.get(0..4).is_some_and(|dword| dword == [0; 4]);
+
+	data.raw_types().into_iter().any(|raw_type| match raw_type {
+		EXCLUDE_FROM_MONITOR => true,
+		KDE_PASSWORD_HINT => data
+			.get_raw_data(raw_type)
+			.map_or(true, |bytes| bytes.trim_ascii() == b"secret"),
+		CAN_INCLUDE_IN_HISTORY | CAN_UPLOAD_TO_CLOUD => {
+			data.get_raw_data(raw_type).map_or(true, is_zero_dword)
+		}
+		_ => false,
+	})
+}
+
+/// Offers the content of `T` together with the hints of the current platform.
+#[cfg(feature = "unstable_write")]
+pub(crate) struct Sensitive<T>(pub T);
+
+#[cfg(feature = "unstable_write")]
+impl<T: WriteToClipboard> WriteToClipboard for Sensitive<T> {
+	fn viable_conversions(&self) -> Vec<String> {
+		let mut conversions = self.0.viable_conversions();
+		conversions.extend(HINT_TYPES.iter().map(|raw_type| raw_type.to_string()));
+		conversions
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		match mime_type {
+			KDE_PASSWORD_HINT => Some(b"secret".to_vec()),
+			EXCLUDE_FROM_MONITOR | CAN_INCLUDE_IN_HISTORY | CAN_UPLOAD_TO_CLOUD => {
+				Some(0u32.to_le_bytes().to_vec())
+			}
+			_ => self.0.convert_to(mime_type),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::ClipboardSnapshot;
+
+	fn snapshot(entries: &[(&str, &[u8])]) -> ClipboardSnapshot {
+		ClipboardSnapshot::new(
+			entries
+				.iter()
+				.map(|(raw_type, bytes)| (raw_type.to_string(), bytes.to_vec()))
+				.collect(),
+		)
+	}
+
+	#[test]
+	fn reads_hints() {
+		let sensitive =
+			|entries: &[(&str, &[u8])]| snapshot(entries).as_data_access().is_sensitive();
+
+		assert!(!sensitive(&[("UTF8_STRING", b"hunter2")]));
+		assert!(sensitive(&[("x-kde-passwordManagerHint", b"secret")]));
+		assert!(!sensitive(&[("x-kde-passwordManagerHint", b"public")]));
+		assert!(sensitive(&[(
+			"CanIncludeInClipboardHistory",
+			&[0, 0, 0, 0]
+		)]));
+		assert!(!sensitive(&[(
+			"CanIncludeInClipboardHistory",
+			&[1, 0, 0, 0]
+		)]));
+		assert!(sensitive(&[(
+			"ExcludeClipboardContentFromMonitorProcessing",
+			b""
+		)]));
+	}
+
+	#[cfg(feature = "unstable_write")]
+	#[test]
+	fn writes_hints() {
+		use super::Sensitive;
+		use crate::WriteToClipboard;
+
+		let data = Sensitive("hunter2".to_string());
+		let entries = data
+			.viable_conversions()
+			.into_iter()
+			.map(|raw_type| {
+				let bytes = data.convert_to(&raw_type).unwrap();
+				(raw_type, bytes)
+			})
+			.collect();
+		let snapshot = ClipboardSnapshot::new(entries);
+
+		assert!(snapshot.as_data_access().is_sensitive());
+		assert_eq!(snapshot.read_data::<String>().as_deref(), Some("hunter2"));
+	}
+}
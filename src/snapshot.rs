@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use crate::{
+	ClipboardBackend, ClipboardError, DataAccess, DataAccessInternal, ReadFromClipboard,
+	WriteToClipboard, internal::InternalDataAccess,
+};
+
+/// Where the content of a [`ClipboardSnapshot`] was taken from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotOrigin {
+	pub backend: ClipboardBackend,
+	/// The operating system, as in [`std::env::consts::OS`], or "web".
+	pub platform: String,
+}
+
+impl SnapshotOrigin {
+	pub fn current(backend: ClipboardBackend) -> Self {
+		let platform = if cfg!(target_arch = "wasm32") {
+			"web"
+		} else {
+			std::env::consts::OS
+		};
+
+		Self {
+			backend,
+			platform: platform.to_string(),
+		}
+	}
+
+	/// Whether the raw types use the Windows clipboard format names.
+	pub fn uses_windows_names(&self) -> bool {
+		self.platform == "windows"
+	}
+}
+
+#[derive(Debug)]
+struct SnapshotData {
+	mime_types: Vec<String>,
+	data: Vec<Vec<u8>>,
+	origin: SnapshotOrigin,
+}
+
+/// Owned copy of clipboard content, which can outlive the [`crate::ClipboardHandler`] callback.
+///
+/// Cloning is cheap, as the fetched bytes are shared between clones.
+#[derive(Debug, Clone)]
+pub struct ClipboardSnapshot {
+	inner: Arc<SnapshotData>,
+}
+
+impl ClipboardSnapshot {
+	/// Creates a snapshot from data produced by the application itself.
+	pub fn new(entries: Vec<(String, Vec<u8>)>) -> Self {
+		Self::with_origin(
+			entries,
+			SnapshotOrigin::current(ClipboardBackend::Application),
+		)
+	}
+
+	pub fn with_origin(entries: Vec<(String, Vec<u8>)>, origin: SnapshotOrigin) -> Self {
+		let (mime_types, data) = entries.into_iter().unzip();
+		Self {
+			inner: Arc::new(SnapshotData {
+				mime_types,
+				data,
+				origin,
+			}),
+		}
+	}
+
+	pub fn origin(&self) -> &SnapshotOrigin {
+		&self.inner.origin
+	}
+
+	pub fn entries(&self) -> impl Iterator<Item = (&str, &[u8])> {
+		self.inner
+			.mime_types
+			.iter()
+			.zip(self.inner.data.iter())
+			.map(|(mime_type, data)| (mime_type.as_str(), data.as_slice()))
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.inner.mime_types.is_empty()
+	}
+
+	pub fn raw_types(&self) -> Vec<&str> {
+		self.inner.mime_types.iter().map(|ty| ty.as_str()).collect()
+	}
+
+	pub fn get_raw_data(&self, raw_type: &str) -> Result<Vec<u8>, ClipboardError> {
+		self.get(raw_type)
+			.map(|data| data.to_vec())
+			.ok_or(ClipboardError::FormatNotAvailable)
+	}
+
+	pub fn is_available<T: ReadFromClipboard>(&self) -> bool {
+		T::is_available(&self.raw_types())
+	}
+
+	pub fn read_data<T: ReadFromClipboard>(&self) -> Option<T> {
+		T::read(&self.as_data_access())
+	}
+
+	/// Wraps the snapshot into a [`DataAccess`], so it can be passed to code expecting one.
+	pub fn as_data_access(&self) -> DataAccess {
+		DataAccess {
+			internal: DataAccessInternal::Snapshot(self.clone()),
+		}
+	}
+
+	fn get(&self, raw_type: &str) -> Option<&[u8]> {
+		self.entries()
+			.find(|(mime_type, _)| *mime_type == raw_type)
+			.map(|(_, data)| data)
+	}
+}
+
+impl Default for ClipboardSnapshot {
+	fn default() -> Self {
+		Self::new(Vec::new())
+	}
+}
+
+impl InternalDataAccess for ClipboardSnapshot {
+	fn backend(&self) -> ClipboardBackend {
+		self.inner.origin.backend
+	}
+
+	fn mime_types(&self) -> &[String] {
+		&self.inner.mime_types
+	}
+
+	fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError> {
+		ClipboardSnapshot::get_raw_data(self, mime_type)
+	}
+}
+
+impl WriteToClipboard for ClipboardSnapshot {
+	fn viable_conversions(&self) -> Vec<String> {
+		self.inner.mime_types.clone()
+	}
+
+	fn convert_to(&self, mime_type: &str) -> Option<Vec<u8>> {
+		self.get(mime_type).map(|data| data.to_vec())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn snapshot() -> ClipboardSnapshot {
+		ClipboardSnapshot::new(vec![
+			("text/plain;charset=utf-8".to_string(), b"hello".to_vec()),
+			("image/png".to_string(), vec![0x89, b'P', b'N', b'G']),
+		])
+	}
+
+	#[test]
+	fn keeps_entries_in_order() {
+		let snapshot = snapshot();
+		assert_eq!(
+			snapshot.raw_types(),
+			["text/plain;charset=utf-8", "image/png"]
+		);
+		assert_eq!(
+			snapshot
+				.entries()
+				.map(|(_, data)| data.len())
+				.collect::<Vec<_>>(),
+			[5, 4]
+		);
+		assert!(!snapshot.is_empty());
+		assert!(ClipboardSnapshot::default().is_empty());
+	}
+
+	#[test]
+	fn returns_raw_data() {
+		let snapshot = snapshot();
+		assert_eq!(snapshot.get_raw_data("image/png").unwrap(), b"\x89PNG");
+		assert!(matches!(
+			snapshot.get_raw_data("text/html"),
+			Err(ClipboardError::FormatNotAvailable)
+		));
+	}
+
+	#[test]
+	fn reads_data() {
+		let snapshot = snapshot();
+		assert!(snapshot.is_available::<String>());
+		assert_eq!(snapshot.read_data::<String>().as_deref(), Some("hello"));
+		assert_eq!(
+			snapshot.as_data_access().read_data::<String>().as_deref(),
+			Some("hello")
+		);
+	}
+
+	#[test]
+	fn uses_the_application_origin() {
+		let origin = snapshot().origin().clone();
+		assert_eq!(origin.backend, ClipboardBackend::Application);
+		assert_eq!(origin.platform, std::env::consts::OS);
+		assert_eq!(origin.uses_windows_names(), cfg!(target_os = "windows"));
+	}
+
+	#[test]
+	fn skips_selection_targets() {
+		let data = ClipboardSnapshot::new(vec![
+			("TARGETS".to_string(), vec![1, 0, 0, 0]),
+			("text/plain;charset=utf-8".to_string(), b"hello".to_vec()),
+			("DELETE".to_string(), Vec::new()),
+			("MULTIPLE".to_string(), Vec::new()),
+		])
+		.as_data_access();
+
+		assert_eq!(
+			data.snapshot(None).raw_types(),
+			["text/plain;charset=utf-8"]
+		);
+		assert_eq!(
+			data.snapshot(Some(&["TARGETS", "text/plain;charset=utf-8"]))
+				.raw_types(),
+			["text/plain;charset=utf-8"]
+		);
+	}
+
+	#[test]
+	fn clones_share_data() {
+		let snapshot = snapshot();
+		let clone = snapshot.clone();
+		assert!(Arc::ptr_eq(&snapshot.inner, &clone.inner));
+	}
+}
@@ -0,0 +1,302 @@
+//! On-disk format of [`ClipboardSnapshot`] dumps.
+//!
+//! A dump is a UTF-8 JSON document:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "origin": { "backend": "windows", "platform": "windows" },
+//!   "entries": [
+//!     { "mime_type": "CF_UNICODETEXT", "data": "680069000000" }
+//!   ]
+//! }
+//! ```
+//!
+//! - `version` is the version of the dump format, currently `1`.
+//! - `origin.backend` is one of `x11`, `wayland`, `windows`, `web` or `application`.
+//! - `origin.platform` is the operating system the dump was taken on, or `web`.
+//! - `entries` are in the order the source offered them. `data` holds the raw bytes as
+//!   lowercase hexadecimal.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de, ser::SerializeStruct};
+
+use crate::{
+	ClipboardSnapshot, SnapshotOrigin,
+	mime_aliases::{self, AliasOrigin},
+};
+
+const DUMP_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct EntryRef<'a> {
+	mime_type: &'a str,
+	#[serde(serialize_with = "serialize_hex")]
+	data: &'a [u8],
+}
+
+#[derive(Deserialize)]
+struct Entry {
+	mime_type: String,
+	#[serde(deserialize_with = "deserialize_hex")]
+	data: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct Dump {
+	version: u32,
+	origin: SnapshotOrigin,
+	entries: Vec<Entry>,
+}
+
+fn serialize_hex<S: Serializer>(data: &&[u8], serializer: S) -> Result<S::Ok, S::Error> {
+	let mut hex = String::with_capacity(data.len() * 2);
+	for byte in data.iter() {
+		hex.push_str(&format!("{byte:02x}"));
+	}
+
+	serializer.serialize_str(&hex)
+}
+
+fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+	let hex = String::deserialize(deserializer)?;
+	if hex.len() % 2 != 0 {
+		return Err(de::Error::custom("hex data has an odd length"));
+	}
+
+	(0..hex.len())
+		.step_by(2)
+		.map(|index| {
+			hex.get(index..index + 2)
+				.and_then(|byte| u8::from_str_radix(byte, 16).ok())
+				.ok_or_else(|| de::Error::custom("invalid hex data"))
+		})
+		.collect()
+}
+
+impl Serialize for ClipboardSnapshot {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let entries: Vec<EntryRef<'_>> = self
+			.entries()
+			.map(|(mime_type, data)| EntryRef { mime_type, data })
+			.collect();
+
+		let mut dump = serializer.serialize_struct("ClipboardSnapshot", 3)?;
+		dump.serialize_field("version", &DUMP_VERSION)?;
+		dump.serialize_field("origin", self.origin())?;
+		dump.serialize_field("entries", &entries)?;
+		dump.end()
+	}
+}
+
+impl<'de> Deserialize<'de> for ClipboardSnapshot {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let dump = Dump::deserialize(deserializer)?;
+		if dump.version > DUMP_VERSION {
+			return Err(de::Error::custom(format!(
+				"unsupported dump version {}",
+				dump.version
+			)));
+		}
+
+		let entries = dump
+			.entries
+			.into_iter()
+			.map(|entry| (entry.mime_type, entry.data))
+			.collect();
+
+		Ok(ClipboardSnapshot::with_origin(entries, dump.origin))
+	}
+}
+
+impl ClipboardSnapshot {
+	/// Writes the snapshot as a dump to `path`. See [`crate::snapshot_dump`] for the format.
+	pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+		let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+		fs::write(path, json)
+	}
+
+	/// Reads a dump from `path` and translates its raw types to the current platform.
+	pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+		let json = fs::read(path)?;
+		let snapshot: Self = serde_json::from_slice(&json)
+			.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+		Ok(snapshot.translated_to_current_platform())
+	}
+
+	/// Renames raw types between Windows and the other platforms, if the snapshot was taken on
+	/// the other kind of platform. The translated snapshot is marked as taken on the current
+	/// platform, so translating it again does nothing.
+	///
+	/// Only formats with a Windows alias in [`mime_aliases`] are renamed, as their bytes do not
+	/// need any conversion. A format is kept under its original name, if the snapshot already
+	/// contains the translated name.
+	pub fn translated_to_current_platform(&self) -> Self {
+		let to_windows = cfg!(target_os = "windows");
+		if self.origin().uses_windows_names() == to_windows {
+			return self.clone();
+		}
+
+		let raw_types = self.raw_types();
+		let entries = self
+			.entries()
+			.map(|(mime_type, data)| {
+				let translated = match to_windows {
+					true => mime_aliases::alias_for(mime_type, AliasOrigin::Windows),
+					false => mime_aliases::all_aliases()
+						.into_iter()
+						.find(|alias| {
+							alias.origin == AliasOrigin::Windows && alias.alias == mime_type
+						})
+						.map(|alias| alias.canonical),
+				}
+				.filter(|translated| !raw_types.contains(&translated.as_str()))
+				.unwrap_or_else(|| mime_type.to_string());
+
+				(translated, data.to_vec())
+			})
+			.collect();
+
+		let origin = SnapshotOrigin {
+			backend: self.origin().backend,
+			platform: SnapshotOrigin::current(self.origin().backend).platform,
+		};
+		ClipboardSnapshot::with_origin(entries, origin)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ClipboardBackend;
+
+	fn windows_snapshot() -> ClipboardSnapshot {
+		let origin = SnapshotOrigin {
+			backend: ClipboardBackend::Windows,
+			platform: "windows".to_string(),
+		};
+		ClipboardSnapshot::with_origin(
+			vec![
+				("PNG".to_string(), vec![0x89, b'P', b'N', b'G']),
+				("CF_UNICODETEXT".to_string(), vec![b'h', 0, b'i', 0, 0, 0]),
+				("HTML Format".to_string(), b"<b>hi</b>".to_vec()),
+			],
+			origin,
+		)
+	}
+
+	fn entries(snapshot: &ClipboardSnapshot) -> Vec<(String, Vec<u8>)> {
+		snapshot
+			.entries()
+			.map(|(mime_type, data)| (mime_type.to_string(), data.to_vec()))
+			.collect()
+	}
+
+	#[test]
+	fn round_trips_through_json() {
+		let snapshot = windows_snapshot();
+		let json = serde_json::to_string(&snapshot).unwrap();
+		assert!(json.contains(r#""version":1"#));
+		assert!(json.contains(r#""data":"680069000000""#));
+
+		let loaded: ClipboardSnapshot = serde_json::from_str(&json).unwrap();
+		assert_eq!(loaded.origin(), snapshot.origin());
+		assert_eq!(entries(&loaded), entries(&snapshot));
+	}
+
+	#[test]
+	fn rejects_newer_versions() {
+		let json = r#"{
+			"version": 2,
+			"origin": { "backend": "x11", "platform": "linux" },
+			"entries": []
+		}"#;
+		let error = serde_json::from_str::<ClipboardSnapshot>(json).unwrap_err();
+		assert!(error.to_string().contains("unsupported dump version 2"));
+	}
+
+	#[test]
+	fn rejects_invalid_hex() {
+		for data in ["abc", "zz"] {
+			let json = format!(
+				r#"{{
+					"version": 1,
+					"origin": {{ "backend": "x11", "platform": "linux" }},
+					"entries": [{{ "mime_type": "text/plain", "data": "{data}" }}]
+				}}"#
+			);
+			assert!(serde_json::from_str::<ClipboardSnapshot>(&json).is_err());
+		}
+	}
+
+	#[test]
+	fn saves_and_loads_files() {
+		let path = std::env::temp_dir().join(format!(
+			"gengine_clipboard_dump_{}.json",
+			std::process::id()
+		));
+		let snapshot = windows_snapshot();
+		snapshot.save(&path).unwrap();
+		let loaded = ClipboardSnapshot::load(&path);
+		fs::remove_file(&path).unwrap();
+
+		let loaded = loaded.unwrap();
+		let translated = snapshot.translated_to_current_platform();
+		assert_eq!(entries(&loaded), entries(&translated));
+		assert_eq!(loaded.origin(), translated.origin());
+	}
+
+	#[test]
+	fn load_reports_invalid_data() {
+		let path = std::env::temp_dir().join(format!(
+			"gengine_clipboard_invalid_dump_{}.json",
+			std::process::id()
+		));
+		fs::write(&path, b"not json").unwrap();
+		let error = ClipboardSnapshot::load(&path).unwrap_err();
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+	}
+
+	#[cfg(not(target_os = "windows"))]
+	#[test]
+	fn translates_windows_names() {
+		let translated = windows_snapshot().translated_to_current_platform();
+		assert_eq!(
+			translated.raw_types(),
+			["image/png", "text/plain;charset=utf-16le", "HTML Format"],
+			"formats without an alias keep their name"
+		);
+		assert_eq!(translated.get_raw_data("image/png").unwrap(), b"\x89PNG");
+		assert_eq!(
+			translated.origin(),
+			&SnapshotOrigin::current(ClipboardBackend::Windows)
+		);
+
+		let again = translated.translated_to_current_platform();
+		assert_eq!(entries(&again), entries(&translated));
+		assert_eq!(again.origin(), translated.origin());
+	}
+
+	#[cfg(not(target_os = "windows"))]
+	#[test]
+	fn keeps_names_that_already_exist() {
+		let origin = SnapshotOrigin {
+			backend: ClipboardBackend::Windows,
+			platform: "windows".to_string(),
+		};
+		let snapshot = ClipboardSnapshot::with_origin(
+			vec![
+				("PNG".to_string(), vec![1]),
+				("image/png".to_string(), vec![2]),
+			],
+			origin,
+		);
+
+		let translated = snapshot.translated_to_current_platform();
+		assert_eq!(translated.raw_types(), ["PNG", "image/png"]);
+	}
+}
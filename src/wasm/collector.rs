@@ -81,9 +81,7 @@ impl Collector {
 					.map(|(mime_type, _)| mime_type.clone())
 					.collect();
 				let data_access = WasmDataAccess::new(mime_types, collected.data);
-				let data_access = crate::DataAccess {
-					internal: data_access,
-				};
+				let data_access = crate::DataAccess::new(data_access);
 
 				handler.handle_event(ClipboardEvent::PasteResult {
 					data: &data_access,
@@ -11,8 +11,13 @@ use crate::{
 	platform::collector::{Collector, CollectorHandle},
 };
 
+pub use crate::ClipboardSnapshot as DataAccessHandle;
 pub use pasta_data_access::WasmDataAccess as DataAccess;
 
+/// Persistent writes are not supported on the web.
+#[cfg(feature = "unstable_write")]
+pub fn run_persistent_server_if_requested() {}
+
 pub struct Clipboard {
 	_handle: CollectorHandle,
 }
@@ -84,7 +89,20 @@ impl InternalClipboard for Clipboard {
 	}
 
 	#[cfg(feature = "unstable_write")]
-	fn write(&self, data: crate::ClipboardData) {
-		todo!()
+	fn write<T: crate::WriteToClipboard>(&self, _data: T) {
+		log::error!("Failed to write to the clipboard: writing is not supported on the web yet.");
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_expiring<T: crate::WriteToClipboard>(&self, data: T, _timeout: std::time::Duration) {
+		self.write(data);
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn persist(&self) {}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_persistent<T: crate::WriteToClipboard>(&self, _data: T) -> Result<(), ClipboardError> {
+		Err(ClipboardError::Unsupported)
 	}
 }
@@ -1,6 +1,6 @@
 use js_sys::Uint8Array;
 
-use crate::{ClipboardError, internal::InternalDataAccess};
+use crate::{ClipboardBackend, ClipboardError, ClipboardSnapshot, internal::InternalDataAccess};
 
 pub struct WasmDataAccess {
 	mime_types: Vec<String>,
@@ -11,9 +11,24 @@ impl WasmDataAccess {
 	pub fn new(mime_types: Vec<String>, data: Vec<(String, Uint8Array)>) -> Self {
 		Self { mime_types, data }
 	}
+
+	/// All data is already on hand after a paste event, so the handle is a snapshot of it.
+	pub fn handle(&self) -> ClipboardSnapshot {
+		let entries = self
+			.data
+			.iter()
+			.map(|(mime_type, data)| (mime_type.clone(), data.to_vec()))
+			.collect();
+
+		ClipboardSnapshot::new(entries)
+	}
 }
 
 impl InternalDataAccess for WasmDataAccess {
+	fn backend(&self) -> ClipboardBackend {
+		ClipboardBackend::Web
+	}
+
 	fn mime_types(&self) -> &[String] {
 		&self.mime_types
 	}
@@ -0,0 +1,133 @@
+use std::cell::Cell;
+
+#[cfg(feature = "unstable_write")]
+use windows::Win32::UI::WindowsAndMessaging::{
+	WM_DESTROYCLIPBOARD, WM_RENDERALLFORMATS, WM_RENDERFORMAT,
+};
+use windows::{
+	Win32::{
+		Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+		System::{
+			DataExchange::{AddClipboardFormatListener, RemoveClipboardFormatListener},
+			LibraryLoader::GetModuleHandleW,
+		},
+		UI::WindowsAndMessaging::{
+			CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, HWND_MESSAGE, MSG,
+			PM_REMOVE, PeekMessageW, RegisterClassW, WINDOW_EX_STYLE, WINDOW_STYLE,
+			WM_CLIPBOARDUPDATE, WNDCLASSW,
+		},
+	},
+	core::{PCWSTR, w},
+};
+
+#[cfg(feature = "unstable_write")]
+use crate::platform::write_data;
+
+const CLASS_NAME: PCWSTR = w!("gengine_clipboard");
+
+thread_local! {
+	/// Set by the window procedure, which has no other way to reach the clipboard thread.
+	static CLIPBOARD_CHANGED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Message-only window of the clipboard thread, which is told about clipboard changes.
+pub struct ClipboardWindow {
+	window: HWND,
+}
+
+impl ClipboardWindow {
+	/// # Warning
+	///
+	/// Only use on the clipboard thread, which has to dispatch the messages of the window.
+	pub fn new() -> windows::core::Result<Self> {
+		let instance = unsafe { GetModuleHandleW(None) }?;
+		let class = WNDCLASSW {
+			lpfnWndProc: Some(window_proc),
+			hInstance: instance.into(),
+			lpszClassName: CLASS_NAME,
+			..Default::default()
+		};
+		// Fails, if an earlier clipboard registered the class already.
+		unsafe { RegisterClassW(&class) };
+
+		let window = unsafe {
+			CreateWindowExW(
+				WINDOW_EX_STYLE::default(),
+				CLASS_NAME,
+				PCWSTR::null(),
+				WINDOW_STYLE::default(),
+				0,
+				0,
+				0,
+				0,
+				Some(HWND_MESSAGE),
+				None,
+				Some(instance.into()),
+				None,
+			)
+		}?;
+		let window = Self { window };
+		unsafe { AddClipboardFormatListener(window.window) }?;
+
+		Ok(window)
+	}
+
+	/// Owner of the formats written by the clipboard thread.
+	#[cfg(feature = "unstable_write")]
+	pub fn handle(&self) -> HWND {
+		self.window
+	}
+}
+
+/// Announced formats are rendered before the window is destroyed.
+impl Drop for ClipboardWindow {
+	fn drop(&mut self) {
+		unsafe {
+			let _ = RemoveClipboardFormatListener(self.window);
+			let _ = DestroyWindow(self.window);
+		}
+	}
+}
+
+/// Dispatches all queued messages of the current thread to their windows.
+pub fn dispatch_messages() {
+	let mut message = MSG::default();
+	while unsafe { PeekMessageW(&mut message, None, 0, 0, PM_REMOVE) }.as_bool() {
+		unsafe { DispatchMessageW(&message) };
+	}
+}
+
+/// Whether the clipboard changed since the last call.
+pub fn take_clipboard_changed() -> bool {
+	CLIPBOARD_CHANGED.with(|changed| changed.replace(false))
+}
+
+unsafe extern "system" fn window_proc(
+	window: HWND,
+	message: u32,
+	wparam: WPARAM,
+	lparam: LPARAM,
+) -> LRESULT {
+	match message {
+		WM_CLIPBOARDUPDATE => {
+			CLIPBOARD_CHANGED.with(|changed| changed.set(true));
+			LRESULT(0)
+		}
+		#[cfg(feature = "unstable_write")]
+		WM_RENDERFORMAT => {
+			write_data::render_format(wparam.0 as u32);
+			LRESULT(0)
+		}
+		#[cfg(feature = "unstable_write")]
+		WM_RENDERALLFORMATS => {
+			write_data::render_all_formats(window);
+			LRESULT(0)
+		}
+		#[cfg(feature = "unstable_write")]
+		WM_DESTROYCLIPBOARD => {
+			write_data::discard_pending();
+			LRESULT(0)
+		}
+		_ => unsafe { DefWindowProcW(window, message, wparam, lparam) },
+	}
+}
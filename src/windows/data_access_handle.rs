@@ -0,0 +1,64 @@
+use std::{
+	sync::mpsc,
+	thread::{self, ThreadId},
+};
+
+use crate::{
+	ClipboardBackend, ClipboardError,
+	internal::InternalDataAccess,
+	platform::{CommandSender, ThreadCommand},
+};
+
+#[derive(Clone)]
+pub struct WindowsDataAccessHandle {
+	mime_types: Vec<String>,
+	sequence_number: u32,
+	sender: CommandSender,
+	thread: ThreadId,
+}
+
+impl WindowsDataAccessHandle {
+	/// # Warning
+	///
+	/// Only use on the clipboard thread.
+	pub fn new(mime_types: Vec<String>, sequence_number: u32, sender: CommandSender) -> Self {
+		Self {
+			mime_types,
+			sequence_number,
+			sender,
+			thread: thread::current().id(),
+		}
+	}
+}
+
+impl InternalDataAccess for WindowsDataAccessHandle {
+	fn backend(&self) -> ClipboardBackend {
+		ClipboardBackend::Windows
+	}
+
+	fn mime_types(&self) -> &[String] {
+		&self.mime_types
+	}
+
+	fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError> {
+		// The clipboard thread would wait for itself.
+		if thread::current().id() == self.thread {
+			return Err(ClipboardError::InUse);
+		}
+
+		let (reply, receiver) = mpsc::channel();
+		let command = ThreadCommand::GetHandleData {
+			mime_type: mime_type.to_string(),
+			sequence_number: self.sequence_number,
+			reply,
+		};
+
+		if self.sender.send(command).is_err() {
+			return Err(ClipboardError::ClipboardDataUnavailable);
+		}
+
+		receiver
+			.recv()
+			.unwrap_or(Err(ClipboardError::ClipboardDataUnavailable))
+	}
+}
@@ -1,24 +1,106 @@
+mod clipboard_window;
+mod data_access_handle;
 mod format_conversion;
 mod paste_data_access;
+#[cfg(feature = "unstable_write")]
+mod write_data;
 
+#[cfg(feature = "unstable_write")]
+use crate::WriteToClipboard;
 use crate::{
-	ClipboardEvent, ClipboardEventSource, ClipboardHandler, InternalClipboard,
-	platform::paste_data_access::WindowsDataAccess,
+	ClipboardError, ClipboardEvent, ClipboardEventSource, ClipboardHandler, InternalClipboard,
+	platform::{
+		clipboard_window::ClipboardWindow,
+		paste_data_access::{WindowsDataAccess, read_format},
+	},
 };
+#[cfg(feature = "unstable_write")]
+use std::time::Instant;
 use std::{
-	sync::mpsc::{self, Sender},
+	sync::{
+		Arc,
+		mpsc::{self, Receiver, SendError, Sender},
+	},
 	thread::{self, JoinHandle},
+	time::Duration,
+};
+use windows::{
+	Win32::{
+		Foundation::{CloseHandle, HANDLE},
+		System::Threading::{CreateEventW, INFINITE, SetEvent},
+		UI::WindowsAndMessaging::{MsgWaitForMultipleObjects, QS_ALLINPUT},
+	},
+	core::PCWSTR,
 };
 
+pub use data_access_handle::WindowsDataAccessHandle as DataAccessHandle;
 pub use paste_data_access::WindowsDataAccess as DataAccess;
 
+/// Written data is handed to the system clipboard, so there is no server.
+#[cfg(feature = "unstable_write")]
+pub fn run_persistent_server_if_requested() {}
+
 enum ThreadCommand {
 	GetData,
+	GetHandleData {
+		mime_type: String,
+		sequence_number: u32,
+		reply: Sender<Result<Vec<u8>, ClipboardError>>,
+	},
+	/// The clipboard is emptied after `timeout`, if it is set.
+	#[cfg(feature = "unstable_write")]
+	Write {
+		data: Box<dyn WriteToClipboard>,
+		timeout: Option<Duration>,
+	},
+	#[cfg(feature = "unstable_write")]
+	Persist,
 	Exit,
 }
 
-pub struct Clipboard {
+/// Auto-reset event, which wakes up the clipboard thread while it waits for window messages.
+struct WakeEvent(HANDLE);
+
+// Events can be signalled from any thread.
+unsafe impl Send for WakeEvent {}
+unsafe impl Sync for WakeEvent {}
+
+impl Drop for WakeEvent {
+	fn drop(&mut self) {
+		let _ = unsafe { CloseHandle(self.0) };
+	}
+}
+
+/// Sends commands to the clipboard thread, and wakes it up while it waits for window messages.
+#[derive(Clone)]
+pub struct CommandSender {
 	sender: Sender<ThreadCommand>,
+	wake: Arc<WakeEvent>,
+}
+
+impl CommandSender {
+	fn new() -> (Self, Receiver<ThreadCommand>, Arc<WakeEvent>) {
+		let event = unsafe { CreateEventW(None, false, false, PCWSTR::null()) }
+			.expect("Failed to create an event for the Windows clipboard thread.");
+		let wake = Arc::new(WakeEvent(event));
+
+		let (sender, receiver) = mpsc::channel();
+		let command_sender = Self {
+			sender,
+			wake: wake.clone(),
+		};
+		(command_sender, receiver, wake)
+	}
+
+	fn send(&self, command: ThreadCommand) -> Result<(), SendError<ThreadCommand>> {
+		self.sender.send(command)?;
+		let _ = unsafe { SetEvent(self.wake.0) };
+		Ok(())
+	}
+}
+
+pub struct Clipboard {
+	sender: CommandSender,
 	join_handle: Option<JoinHandle<()>>,
 }
 
@@ -27,38 +109,105 @@ impl InternalClipboard for Clipboard {
 		_display_handle: &dyn raw_window_handle::HasDisplayHandle,
 		mut handler: T,
 	) -> Self {
-		let (sender, receiver) = mpsc::channel();
+		let (sender, receiver, wake) = CommandSender::new();
+		let thread_sender = sender.clone();
 		let join_handle = Some(thread::spawn(move || {
+			let window = ClipboardWindow::new()
+				.inspect_err(|error| {
+					log::error!("Failed to listen for Windows clipboard changes: {error}")
+				})
+				.ok();
 			let mut event_conut = 0;
+			// The deadline of an expiring write, with the sequence number of the written content.
+			#[cfg(feature = "unstable_write")]
+			let mut expiring: Option<(Instant, u32)> = None;
 
-			for command in receiver {
-				match command {
-					ThreadCommand::GetData => {
-						let source = ClipboardEventSource { value: event_conut };
-						event_conut += 1;
-						handler.handle_event(ClipboardEvent::StartedPasteHandling { source });
-
-						let data_access = match WindowsDataAccess::new() {
-							Ok(data_access) => data_access,
-							Err(error) => {
-								handler.handle_event(ClipboardEvent::FailedPasteHandling {
-									source,
-									error,
-								});
-								return;
+			'thread: loop {
+				for command in receiver.try_iter() {
+					match command {
+						ThreadCommand::GetData => {
+							let source = ClipboardEventSource { value: event_conut };
+							event_conut += 1;
+							handler.handle_event(ClipboardEvent::StartedPasteHandling { source });
+
+							let data_access = WindowsDataAccess::new(thread_sender.clone());
+							let data_access = crate::DataAccess::new(data_access);
+							handler.handle_event(ClipboardEvent::PasteResult {
+								data: &data_access,
+								source,
+							});
+						}
+						ThreadCommand::GetHandleData {
+							mime_type,
+							sequence_number,
+							reply,
+						} => {
+							let _ = reply.send(read_format(&mime_type, sequence_number));
+						}
+						#[cfg(feature = "unstable_write")]
+						ThreadCommand::Write { data, timeout } => {
+							expiring = None;
+							let owner = window.as_ref().map(ClipboardWindow::handle);
+							match write_data::write_data(data, owner) {
+								Ok(sequence_number) => {
+									expiring = timeout
+										.map(|timeout| (Instant::now() + timeout, sequence_number));
+								}
+								Err(error) => {
+									log::error!(
+										"Failed to write to the Windows clipboard: {error:?}"
+									)
+								}
+							}
+						}
+						#[cfg(feature = "unstable_write")]
+						ThreadCommand::Persist => {
+							if let Some(window) = &window {
+								write_data::render_all_formats(window.handle());
 							}
-						};
-						let data_access = crate::DataAccess {
-							internal: data_access,
-						};
-
-						handler.handle_event(ClipboardEvent::PasteResult {
-							data: &data_access,
-							source,
-						});
+						}
+						ThreadCommand::Exit => break 'thread,
+					}
+				}
+
+				#[cfg(feature = "unstable_write")]
+				if let Some((expires_at, sequence_number)) = expiring
+					&& Instant::now() >= expires_at
+				{
+					expiring = None;
+					match write_data::clear_unchanged(sequence_number) {
+						Ok(true) => {
+							let source = ClipboardEventSource { value: event_conut };
+							event_conut += 1;
+							handler.handle_event(ClipboardEvent::Expired { source });
+						}
+						Ok(false) => {}
+						Err(error) => {
+							log::error!("Failed to clear the Windows clipboard: {error:?}")
+						}
 					}
-					ThreadCommand::Exit => break,
 				}
+
+				// The clipboard is only opened, once the handler reads from it.
+				if clipboard_window::take_clipboard_changed() {
+					let source = ClipboardEventSource { value: event_conut };
+					event_conut += 1;
+
+					let data_access = WindowsDataAccess::new(thread_sender.clone());
+					let data_access = crate::DataAccess::new(data_access);
+					handler.handle_event(ClipboardEvent::ClipboardChanged {
+						data: &data_access,
+						source,
+					});
+				}
+
+				#[cfg(feature = "unstable_write")]
+				let timeout = expiring
+					.map(|(expires_at, _)| expires_at.saturating_duration_since(Instant::now()));
+				#[cfg(not(feature = "unstable_write"))]
+				let timeout = None;
+
+				wait(&wake, timeout);
 			}
 		}));
 
@@ -73,8 +222,32 @@ impl InternalClipboard for Clipboard {
 	}
 
 	#[cfg(feature = "unstable_write")]
-	fn write(&self, _data: ClipboardData) {
-		unimplemented!("Clipboard write not implemented yet.");
+	fn write<T: WriteToClipboard>(&self, data: T) {
+		let _ = self.sender.send(ThreadCommand::Write {
+			data: Box::new(data),
+			timeout: None,
+		});
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_expiring<T: WriteToClipboard>(&self, data: T, timeout: Duration) {
+		let _ = self.sender.send(ThreadCommand::Write {
+			data: Box::new(data),
+			timeout: Some(timeout),
+		});
+	}
+
+	/// Renders all announced formats, so the system clipboard holds the data itself.
+	#[cfg(feature = "unstable_write")]
+	fn persist(&self) {
+		let _ = self.sender.send(ThreadCommand::Persist);
+	}
+
+	#[cfg(feature = "unstable_write")]
+	fn write_persistent<T: WriteToClipboard>(&self, data: T) -> Result<(), ClipboardError> {
+		self.write(data);
+		self.persist();
+		Ok(())
 	}
 }
 
@@ -86,3 +259,18 @@ impl Drop for Clipboard {
 		}
 	}
 }
+
+/// Blocks until window messages or commands arrive, or `timeout` passes, and dispatches the
+/// messages.
+fn wait(wake: &WakeEvent, timeout: Option<Duration>) {
+	// Rounded up, so the thread does not spin during the last millisecond.
+	let timeout = timeout.map_or(INFINITE, |timeout| {
+		timeout
+			.as_micros()
+			.div_ceil(1000)
+			.min(u128::from(INFINITE - 1)) as u32
+	});
+	let _ = unsafe { MsgWaitForMultipleObjects(Some(&[wake.0]), false, timeout, QS_ALLINPUT) };
+
+	clipboard_window::dispatch_messages();
+}
@@ -1,119 +1,189 @@
-use std::time::{Duration, Instant};
+use std::{
+	cell::Cell,
+	thread,
+	time::{Duration, Instant},
+};
 
 use windows::Win32::{
-	Foundation::HGLOBAL,
+	Foundation::{HGLOBAL, HWND},
 	System::{
 		DataExchange::{
-			CloseClipboard, CountClipboardFormats, EnumClipboardFormats, GetClipboardData,
-			OpenClipboard,
+			CloseClipboard, CountClipboardFormats, GetClipboardData, GetClipboardSequenceNumber,
+			GetUpdatedClipboardFormats, OpenClipboard,
 		},
 		Memory::{GlobalLock, GlobalSize, GlobalUnlock},
 	},
 };
 
 use crate::{
-	ClipboardError,
+	ClipboardBackend, ClipboardError,
 	internal::InternalDataAccess,
-	platform::format_conversion::{get_format_code, get_format_name},
+	platform::{
+		CommandSender,
+		data_access_handle::WindowsDataAccessHandle,
+		format_conversion::{get_format_code, get_format_name},
+	},
 };
 
 const TIMEOUT_LIMIT: Duration = Duration::from_secs(2);
 
+/// How long to wait before trying again, while another program holds the clipboard.
+const OPEN_RETRY_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Clipboard content, which opens the clipboard on the first read and keeps it open until it is
+/// dropped.
 pub struct WindowsDataAccess {
 	mime_types: Vec<String>,
+	sequence_number: u32,
+	sender: CommandSender,
+	opened: Cell<bool>,
 }
 
-impl WindowsDataAccess {
-	pub fn new() -> Result<Self, ClipboardError> {
-		let start_time = Instant::now();
-		loop {
-			if let Ok(()) = unsafe { OpenClipboard(None) } {
-				break;
-			}
+/// Opens the clipboard, retrying while another program holds it.
+///
+/// Formats written while it is open belong to `window`.
+pub(super) fn open_clipboard(window: Option<HWND>) -> Result<(), ClipboardError> {
+	let start_time = Instant::now();
+	loop {
+		if let Ok(()) = unsafe { OpenClipboard(window) } {
+			return Ok(());
+		}
 
-			if Instant::now() - start_time > TIMEOUT_LIMIT {
-				return Err(ClipboardError::Timeout);
-			}
+		if Instant::now() - start_time > TIMEOUT_LIMIT {
+			return Err(ClipboardError::Timeout);
 		}
+		thread::sleep(OPEN_RETRY_INTERVAL);
+	}
+}
+
+/// Reads `mime_type` with the clipboard opened only for this read, if the content still is the
+/// one of `sequence_number`.
+pub(super) fn read_format(
+	mime_type: &str,
+	sequence_number: u32,
+) -> Result<Vec<u8>, ClipboardError> {
+	open_clipboard(None)?;
+	let result = read_opened(mime_type, sequence_number);
+	unsafe {
+		let _ = CloseClipboard();
+	}
+
+	result
+}
+
+/// # Warning
+///
+/// Only use when clipboard is opened.
+fn read_opened(mime_type: &str, sequence_number: u32) -> Result<Vec<u8>, ClipboardError> {
+	// Checked while the clipboard is open, so nobody can write in between.
+	if unsafe { GetClipboardSequenceNumber() } != sequence_number {
+		return Err(ClipboardError::Stale);
+	}
+
+	let format = get_format_code(mime_type);
+	let handle = match unsafe { GetClipboardData(format) } {
+		Ok(handle) => handle,
+		Err(error) => {
+			return Err(ClipboardError::Unknown(format!(
+				"Windows error code {} with message {}.",
+				error.code(),
+				error.message()
+			)));
+		}
+	};
+
+	// Not sure if this can happen
+	if handle.is_invalid() {
+		return Err(ClipboardError::ClipboardDataUnavailable);
+	}
+
+	let global = HGLOBAL(handle.0);
+	let lock_ptr = unsafe { GlobalLock(global) };
+	if lock_ptr.is_null() {
+		return Err(ClipboardError::ClipboardDataUnavailable);
+	}
 
+	let size = unsafe { GlobalSize(global) };
+	if size == 0 {
+		return Err(ClipboardError::ClipboardDataUnavailable);
+	}
+
+	let data = unsafe { std::slice::from_raw_parts(lock_ptr as *const u8, size) };
+	let data = data.to_vec();
+	let _ = unsafe { GlobalUnlock(global) };
+
+	Ok(data)
+}
+
+impl WindowsDataAccess {
+	/// Lists the formats without opening the clipboard.
+	pub fn new(sender: CommandSender) -> Self {
+		// Taken first, so a change while the formats are listed makes reads fail as stale.
+		let sequence_number = unsafe { GetClipboardSequenceNumber() };
 		let mime_types = Self::get_mime_types();
-		Ok(WindowsDataAccess { mime_types })
+		WindowsDataAccess {
+			mime_types,
+			sequence_number,
+			sender,
+			opened: Cell::new(false),
+		}
 	}
 
-	/// # Warning
-	///
-	/// Only use when clipboard is opened.
-	fn get_mime_types() -> Vec<String> {
-		let n_types = unsafe { CountClipboardFormats() };
-
-		let mut formats: Vec<u32> = Vec::new();
-		loop {
-			let previous = formats.last().copied().unwrap_or(0);
-			let next = unsafe { EnumClipboardFormats(previous) };
-			if next != 0 {
-				formats.push(next);
-			} else {
-				break;
-			}
+	pub fn handle(&self) -> WindowsDataAccessHandle {
+		WindowsDataAccessHandle::new(
+			self.mime_types.clone(),
+			self.sequence_number,
+			self.sender.clone(),
+		)
+	}
 
-			if formats.len() as i32 == n_types {
-				break;
+	fn get_mime_types() -> Vec<String> {
+		let mut formats = vec![0; unsafe { CountClipboardFormats() }.max(0) as usize];
+		// Retried, in case formats were added in between.
+		for _ in 0..3 {
+			let mut count = 0;
+			match unsafe { GetUpdatedClipboardFormats(&mut formats, &mut count) } {
+				Ok(()) => {
+					formats.truncate(count as usize);
+					return formats
+						.iter()
+						.map(|format| get_format_name(*format))
+						.collect();
+				}
+				Err(_) if count as usize > formats.len() => formats.resize(count as usize, 0),
+				Err(_) => break,
 			}
 		}
 
-		formats
-			.iter()
-			.map(|format| get_format_name(*format))
-			.collect()
+		Vec::new()
 	}
 }
 
 impl InternalDataAccess for WindowsDataAccess {
+	fn backend(&self) -> ClipboardBackend {
+		ClipboardBackend::Windows
+	}
+
 	fn mime_types(&self) -> &[String] {
 		&self.mime_types
 	}
 
 	fn get_raw_data(&self, mime_type: &str) -> Result<Vec<u8>, ClipboardError> {
-		let format = get_format_code(mime_type);
-		let handle = match unsafe { GetClipboardData(format) } {
-			Ok(handle) => handle,
-			Err(error) => {
-				return Err(ClipboardError::Unknown(format!(
-					"Windows error code {} with message {}.",
-					error.code(),
-					error.message()
-				)));
-			}
-		};
-
-		// Not sure if this can happen
-		if handle.is_invalid() {
-			return Err(ClipboardError::ClipboardDataUnavailable);
-		}
-
-		let global = HGLOBAL(handle.0);
-		let lock_ptr = unsafe { GlobalLock(global) };
-		if lock_ptr.is_null() {
-			return Err(ClipboardError::ClipboardDataUnavailable);
-		}
-
-		let size = unsafe { GlobalSize(global) };
-		if size == 0 {
-			return Err(ClipboardError::ClipboardDataUnavailable);
+		if !self.opened.get() {
+			open_clipboard(None)?;
+			self.opened.set(true);
 		}
 
-		let data = unsafe { std::slice::from_raw_parts(lock_ptr as *const u8, size) };
-		let data = data.to_vec();
-		let _ = unsafe { GlobalUnlock(global) };
-
-		Ok(data)
+		read_opened(mime_type, self.sequence_number)
 	}
 }
 
 impl Drop for WindowsDataAccess {
 	fn drop(&mut self) {
-		unsafe {
-			let _ = CloseClipboard();
+		if self.opened.get() {
+			unsafe {
+				let _ = CloseClipboard();
+			}
 		}
 	}
 }
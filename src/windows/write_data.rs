@@ -0,0 +1,188 @@
+use std::cell::RefCell;
+
+use windows::Win32::{
+	Foundation::{GlobalFree, HANDLE, HWND},
+	System::{
+		DataExchange::{
+			CloseClipboard, EmptyClipboard, GetClipboardOwner, GetClipboardSequenceNumber,
+			OpenClipboard, SetClipboardData,
+		},
+		Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalUnlock},
+	},
+};
+
+use crate::{
+	ClipboardError, WriteToClipboard,
+	platform::{format_conversion::get_format_code, paste_data_access::open_clipboard},
+};
+
+thread_local! {
+	/// Written data, whose formats are only rendered once a paste target asks for them.
+	static PENDING: RefCell<Option<Box<dyn WriteToClipboard>>> = const { RefCell::new(None) };
+}
+
+/// Returns the sequence number of the written content.
+///
+/// With a `window`, the formats are announced without data, and rendered once the window receives
+/// `WM_RENDERFORMAT`.
+pub(super) fn write_data(
+	data: Box<dyn WriteToClipboard>,
+	window: Option<HWND>,
+) -> Result<u32, ClipboardError> {
+	open_clipboard(window)?;
+	// Read before closing, as other programs may write right after.
+	let result = match window {
+		Some(_) => announce_conversions(data),
+		None => set_conversions(data.as_ref()),
+	}
+	.map(|_| unsafe { GetClipboardSequenceNumber() });
+	unsafe {
+		let _ = CloseClipboard();
+	}
+
+	result
+}
+
+/// Empties the clipboard, if its content did not change since `sequence_number`.
+///
+/// Returns whether the clipboard was emptied.
+pub(super) fn clear_unchanged(sequence_number: u32) -> Result<bool, ClipboardError> {
+	open_clipboard(None)?;
+	// Checked while the clipboard is open, so nobody can write in between.
+	let result = if unsafe { GetClipboardSequenceNumber() } == sequence_number {
+		unsafe { EmptyClipboard() }
+			.map(|_| true)
+			.map_err(|_| ClipboardError::SetFailed)
+	} else {
+		Ok(false)
+	};
+	unsafe {
+		let _ = CloseClipboard();
+	}
+
+	result
+}
+
+/// Answers `WM_RENDERFORMAT`, which comes with the clipboard already opened by the paste target.
+pub(super) fn render_format(format: u32) {
+	PENDING.with_borrow(|data| {
+		let Some(data) = data else {
+			return;
+		};
+
+		let mime_type = data
+			.viable_conversions()
+			.into_iter()
+			.find(|mime_type| get_format_code(mime_type) == format);
+		if let Some(mime_type) = mime_type
+			&& let Err(error) = set_conversion(data.as_ref(), &mime_type)
+		{
+			log::error!("Failed to render {mime_type} for the Windows clipboard: {error:?}");
+		}
+	});
+}
+
+/// Renders all formats still announced by `window`, so they outlive it.
+///
+/// Answers `WM_RENDERALLFORMATS`, which is sent before the window is destroyed.
+pub(super) fn render_all_formats(window: HWND) {
+	if unsafe { OpenClipboard(Some(window)) }.is_err() {
+		log::error!("Failed to open the Windows clipboard to render all formats.");
+		return;
+	}
+
+	// Another program may have written since.
+	if unsafe { GetClipboardOwner() }.is_ok_and(|owner| owner == window) {
+		PENDING.with_borrow(|data| {
+			if let Some(data) = data {
+				for mime_type in data.viable_conversions() {
+					if let Err(error) = set_conversion(data.as_ref(), &mime_type) {
+						log::error!(
+							"Failed to render {mime_type} for the Windows clipboard: {error:?}"
+						);
+					}
+				}
+			}
+		});
+	}
+	unsafe {
+		let _ = CloseClipboard();
+	}
+}
+
+/// Answers `WM_DESTROYCLIPBOARD`, which is sent once another write empties the clipboard.
+pub(super) fn discard_pending() {
+	PENDING.take();
+}
+
+/// # Warning
+///
+/// Only use when clipboard is opened.
+fn announce_conversions(data: Box<dyn WriteToClipboard>) -> Result<(), ClipboardError> {
+	// Sends `WM_DESTROYCLIPBOARD` to the previous owner, which may be this thread.
+	if unsafe { EmptyClipboard() }.is_err() {
+		return Err(ClipboardError::SetFailed);
+	}
+
+	let mime_types = data.viable_conversions();
+	PENDING.set(Some(data));
+	for mime_type in mime_types {
+		let format = get_format_code(&mime_type);
+		if unsafe { SetClipboardData(format, None) }.is_err() {
+			return Err(ClipboardError::SetFailed);
+		}
+	}
+
+	Ok(())
+}
+
+/// # Warning
+///
+/// Only use when clipboard is opened.
+fn set_conversions(data: &dyn WriteToClipboard) -> Result<(), ClipboardError> {
+	if unsafe { EmptyClipboard() }.is_err() {
+		return Err(ClipboardError::SetFailed);
+	}
+
+	for mime_type in data.viable_conversions() {
+		set_conversion(data, &mime_type)?;
+	}
+
+	Ok(())
+}
+
+/// Skips the format, if `data` cannot be converted to it.
+///
+/// # Warning
+///
+/// Only use when clipboard is opened.
+fn set_conversion(data: &dyn WriteToClipboard, mime_type: &str) -> Result<(), ClipboardError> {
+	let Some(bytes) = data.convert_to(mime_type) else {
+		return Ok(());
+	};
+
+	let global = match unsafe { GlobalAlloc(GMEM_MOVEABLE, bytes.len().max(1)) } {
+		Ok(global) => global,
+		Err(_) => return Err(ClipboardError::AllocationFailed),
+	};
+
+	let lock_ptr = unsafe { GlobalLock(global) };
+	if lock_ptr.is_null() {
+		let _ = unsafe { GlobalFree(Some(global)) };
+		return Err(ClipboardError::LockFailed);
+	}
+
+	unsafe {
+		std::ptr::copy_nonoverlapping(bytes.as_ptr(), lock_ptr as *mut u8, bytes.len());
+		let _ = GlobalUnlock(global);
+	}
+
+	// On success the system owns the memory, otherwise we still have to free it.
+	let format = get_format_code(mime_type);
+	if unsafe { SetClipboardData(format, Some(HANDLE(global.0))) }.is_err() {
+		let _ = unsafe { GlobalFree(Some(global)) };
+		return Err(ClipboardError::SetFailed);
+	}
+
+	Ok(())
+}
@@ -0,0 +1,101 @@
+use gengine_clipboard::{ClipboardSnapshot, ReadFromClipboard, WriteToClipboard};
+use serde::{Deserialize, Serialize};
+
+const PREFAB_MIME: &str = "application/x-gengine-prefab";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, ReadFromClipboard, WriteToClipboard)]
+#[clipboard(mime = "application/x-gengine-prefab", codec = "json")]
+struct Prefab {
+	name: String,
+	health: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, ReadFromClipboard, WriteToClipboard)]
+#[clipboard(mime = "application/x-gengine-tag")]
+#[clipboard(fallback = String)]
+struct Tag(String);
+
+#[cfg(feature = "bincode")]
+#[derive(Debug, PartialEq, Serialize, Deserialize, ReadFromClipboard, WriteToClipboard)]
+#[clipboard(mime = "application/x-gengine-save", codec = "bincode")]
+struct SaveSlot {
+	slot: u8,
+	name: String,
+}
+
+impl TryFrom<String> for Tag {
+	type Error = ();
+
+	fn try_from(text: String) -> Result<Self, ()> {
+		text.strip_prefix('#')
+			.map(|tag| Tag(tag.to_string()))
+			.ok_or(())
+	}
+}
+
+impl From<&Tag> for String {
+	fn from(tag: &Tag) -> Self {
+		format!("#{}", tag.0)
+	}
+}
+
+#[test]
+fn round_trips_through_a_snapshot() {
+	let prefab = Prefab {
+		name: "crate".to_string(),
+		health: 40,
+	};
+
+	assert_eq!(prefab.viable_conversions(), [PREFAB_MIME]);
+	assert_eq!(prefab.convert_to("text/plain"), None);
+
+	let bytes = prefab.convert_to(PREFAB_MIME).unwrap();
+	let snapshot = ClipboardSnapshot::new(vec![(PREFAB_MIME.to_string(), bytes)]);
+	assert!(snapshot.is_available::<Prefab>());
+	assert_eq!(snapshot.read_data::<Prefab>(), Some(prefab));
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn round_trips_bincode() {
+	let save = SaveSlot {
+		slot: 2,
+		name: "forest".to_string(),
+	};
+
+	let bytes = save.convert_to("application/x-gengine-save").unwrap();
+	assert_eq!(bytes, bincode::serialize(&save).unwrap());
+
+	let snapshot = ClipboardSnapshot::new(vec![("application/x-gengine-save".to_string(), bytes)]);
+	assert_eq!(snapshot.read_data::<SaveSlot>(), Some(save));
+}
+
+#[test]
+fn reads_fallback() {
+	let snapshot = ClipboardSnapshot::new(vec![(
+		"text/plain;charset=utf-8".to_string(),
+		b"#boss".to_vec(),
+	)]);
+	assert!(Tag::is_available(&["UTF8_STRING"]));
+	assert_eq!(snapshot.read_data::<Tag>(), Some(Tag("boss".to_string())));
+
+	let snapshot = ClipboardSnapshot::new(vec![(
+		"text/plain;charset=utf-8".to_string(),
+		b"boss".to_vec(),
+	)]);
+	assert_eq!(snapshot.read_data::<Tag>(), None);
+}
+
+#[test]
+fn writes_fallback() {
+	let tag = Tag("boss".to_string());
+	let conversions = tag.viable_conversions();
+	assert_eq!(conversions[0], "application/x-gengine-tag");
+
+	let text_type = &conversions[1];
+	let snapshot = ClipboardSnapshot::new(vec![(
+		text_type.clone(),
+		tag.convert_to(text_type).unwrap(),
+	)]);
+	assert_eq!(snapshot.read_data::<String>().as_deref(), Some("#boss"));
+}